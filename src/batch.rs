@@ -0,0 +1,224 @@
+use anyhow::Result;
+use colored::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::output::{self, BatchErrorOutput};
+use crate::pipeline::{
+    DEFAULT_JSON_RESULTS, Pipeline, RankOptions, ResultWindow, SearchRequest, search_output,
+};
+use crate::{cli, db, embed, fuzzy, profile, search};
+
+/// Most queries embedded in one model call.
+pub const BATCH_SIZE: usize = 32;
+
+/// One line of `askman batch` input. Other fields (an eval case's `expected_command`, say)
+/// are ignored, so existing JSONL files can be piped in as they are.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BatchQuery {
+    /// Echoed on the result line; any JSON value.
+    #[serde(default)]
+    pub id: Value,
+    pub query: String,
+    pub os: Option<String>,
+    /// Distinct commands to return, as `--limit`.
+    pub limit: Option<usize>,
+}
+
+/// Parses one input line. The `id` is recovered even when the rest is invalid, so the error
+/// line can still be matched to its query.
+pub fn parse_line(line: &str) -> (Value, Result<BatchQuery>) {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return (Value::Null, Err(e.into())),
+    };
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let query = serde_json::from_value::<BatchQuery>(value)
+        .map_err(anyhow::Error::from)
+        .and_then(|query| {
+            if query.limit == Some(0) {
+                anyhow::bail!("limit must be at least 1");
+            }
+            Ok(query)
+        });
+    (id, query)
+}
+
+/// The result line for a query that could not be answered.
+pub fn error_line(id: Value, error: &anyhow::Error) -> BatchErrorOutput {
+    BatchErrorOutput::new(id, error)
+}
+
+/// Reads the next non-empty line, then whatever further complete lines are already buffered,
+/// up to `max`. It never waits for more input once it has a line, so a caller that sends one
+/// query at a time gets each answer before writing the next.
+pub fn read_chunk<R: Read>(reader: &mut BufReader<R>, max: usize) -> std::io::Result<Vec<String>> {
+    let mut chunk = Vec::new();
+    let mut line = String::new();
+    while chunk.len() < max {
+        if !chunk.is_empty() && !reader.buffer().contains(&b'\n') {
+            break;
+        }
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if !line.is_empty() {
+            chunk.push(line.to_string());
+        }
+    }
+    Ok(chunk)
+}
+
+/// Answers one JSON query per stdin line with one `--json` result per stdout line, in order.
+/// Queries that arrive together are embedded together.
+pub fn run_batch(batch_args: &cli::BatchArgs) -> Result<()> {
+    use std::io::Write;
+
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let profile = profile::load_profile()?;
+    let options = RankOptions {
+        json: true,
+        installed_only: batch_args.installed_only,
+        depth: ResultWindow::default().depth(DEFAULT_JSON_RESULTS),
+        max_risk: None,
+    };
+    let mut pipeline = Pipeline::load(
+        &conn,
+        &app_dir,
+        &profile,
+        batch_args.rerank || profile.rerank.enabled,
+        options,
+    )?;
+    if let Some(reason) = &pipeline.rerank_skipped {
+        eprintln!("{}", format!("Reranking skipped: {reason}").yellow());
+    }
+
+    let mut input = BufReader::new(std::io::stdin().lock());
+    let mut stdout = std::io::stdout().lock();
+    loop {
+        let lines = read_chunk(&mut input, BATCH_SIZE)?;
+        if lines.is_empty() {
+            return Ok(());
+        }
+        for result in answer_batch(&mut pipeline, &lines) {
+            writeln!(stdout, "{}", serde_json::to_string(&result)?)?;
+        }
+        stdout.flush()?;
+    }
+}
+
+/// Result lines for one chunk of batch input. Every valid query is embedded in one model
+/// call; a line that fails gets an `error` line instead of stopping the batch.
+pub fn answer_batch(pipeline: &mut Pipeline, lines: &[String]) -> Vec<output::Output> {
+    let mut results: Vec<Option<output::Output>> = vec![None; lines.len()];
+    let mut pending = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let (id, query) = parse_line(line);
+        let prepared = query.and_then(|query| {
+            let (target_os, os_source) = search::resolve_target_os(query.os.as_deref())?;
+            let target_os = search::page_os(pipeline.conn, target_os)?;
+            let corrections = pipeline.corrections(&query.query)?;
+            Ok((query, target_os, os_source, corrections))
+        });
+        match prepared {
+            Ok(prepared) => pending.push((i, id, prepared)),
+            Err(e) => results[i] = Some(output::Output::BatchError(error_line(id, &e))),
+        }
+    }
+
+    let corrected: Vec<String> = pending
+        .iter()
+        .map(|(_, _, (query, _, _, corrections))| {
+            fuzzy::apply_corrections(&query.query, corrections)
+        })
+        .collect();
+    let texts: Vec<&str> = corrected.iter().map(String::as_str).collect();
+    // If the chunk fails as a whole, embed each query alone so only the bad ones fail.
+    let vectors: Vec<Result<Vec<f32>>> = match embed::embed_queries(&pipeline.embedder, &texts) {
+        Ok(vectors) => vectors.into_iter().map(Ok).collect(),
+        Err(_) => texts
+            .iter()
+            .map(|text| embed::embed_query(&pipeline.embedder, text))
+            .collect(),
+    };
+
+    let no_bindings = HashMap::new();
+    for ((i, id, (query, target_os, os_source, corrections)), q_vec) in
+        pending.into_iter().zip(vectors)
+    {
+        let window = ResultWindow {
+            offset: 0,
+            limit: query.limit,
+        };
+        pipeline.options.depth = window.depth(DEFAULT_JSON_RESULTS);
+        let result = q_vec
+            .and_then(|q_vec| {
+                pipeline.search_embedded(&query.query, corrections, &q_vec, target_os)
+            })
+            .map(|outcome| {
+                let request = SearchRequest {
+                    query: &query.query,
+                    target_os,
+                    os_source,
+                    profile: pipeline.profile,
+                    tools: None,
+                    max_risk: None,
+                    window,
+                    bindings: &no_bindings,
+                    verbose: false,
+                };
+                let mut output = search_output(&request, &outcome);
+                output.id = Some(id.clone());
+                output::Output::Search(output)
+            });
+        results[i] =
+            Some(result.unwrap_or_else(|e| output::Output::BatchError(error_line(id, &e))));
+    }
+    results.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_lines_and_keeps_ids_of_bad_ones() {
+        let (id, query) = parse_line(
+            r#"{"id": 7, "query": "extract tar", "os": "osx", "expected_command": "tar"}"#,
+        );
+        assert_eq!(id, json!(7));
+        let query = query.unwrap();
+        assert_eq!(query.query, "extract tar");
+        assert_eq!(query.os.as_deref(), Some("osx"));
+        assert_eq!(query.limit, None);
+
+        let (id, query) = parse_line(r#"{"id": "a", "limit": 2}"#);
+        assert_eq!(id, json!("a"));
+        assert!(query.is_err());
+        let (id, query) = parse_line(r#"{"id": "b", "query": "ls", "limit": 0}"#);
+        assert_eq!(id, json!("b"));
+        assert_eq!(
+            serde_json::to_value(error_line(id, &query.unwrap_err())).unwrap(),
+            json!({"schema_version": 1, "id": "b", "error": "limit must be at least 1"})
+        );
+        assert_eq!(parse_line("{oops").0, Value::Null);
+    }
+
+    #[test]
+    fn chunks_only_buffered_lines() {
+        let input = "a\n\nb\nc\nd\ne\n";
+        // The first fill holds "a\n\nb\n"; the rest arrives with later reads.
+        let mut reader = BufReader::with_capacity(5, input.as_bytes());
+        assert_eq!(read_chunk(&mut reader, 10).unwrap(), vec!["a", "b"]);
+        let mut reader = BufReader::new(input.as_bytes());
+        assert_eq!(read_chunk(&mut reader, 3).unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(read_chunk(&mut reader, 3).unwrap(), vec!["d", "e"]);
+        assert!(read_chunk(&mut reader, 3).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::profile::RankingProfile;
+use crate::search::{self, CmdData};
+
+const STORE_FILE: &str = "calibration.json";
+/// Fewer labeled results than this cannot produce a meaningful fit.
+pub const MIN_SAMPLES: usize = 10;
+
+const METHOD: &str = "platt";
+/// Ridge term keeping Newton steps well-conditioned (and disabled features at zero).
+const RIDGE: f64 = 1e-3;
+const MAX_ITERATIONS: usize = 100;
+/// Results per query that become samples; `--json` reports at most two.
+const SAMPLES_PER_QUERY: usize = 2;
+
+/// One ranked result with its label: was this command the right answer?
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub adjusted_distance: f64,
+    pub coverage: f64,
+    pub correct: bool,
+}
+
+/// Platt-style logistic mapping from (adjusted distance, intent coverage) to the
+/// probability that a result is the correct command.
+/// Monotonic by construction: never rises with distance, never falls with coverage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Calibration {
+    pub method: String,
+    pub intercept: f64,
+    pub distance_weight: f64,
+    pub coverage_weight: f64,
+    /// Labeled results the fit was computed from.
+    pub samples: usize,
+}
+
+impl Calibration {
+    pub fn probability(&self, adjusted_distance: f64, coverage: f64) -> f64 {
+        sigmoid(
+            self.intercept
+                + self.distance_weight * adjusted_distance
+                + self.coverage_weight * coverage,
+        )
+    }
+
+    /// Fits the mapping by Newton's method on Platt's smoothed targets, which keeps the
+    /// coefficients finite even when the labeled set is perfectly separable.
+    pub fn fit(samples: &[Sample]) -> Result<Self> {
+        if samples.len() < MIN_SAMPLES {
+            bail!(
+                "need at least {MIN_SAMPLES} labeled results to calibrate, got {}",
+                samples.len()
+            );
+        }
+        let positives = samples.iter().filter(|s| s.correct).count();
+        if positives == 0 || positives == samples.len() {
+            bail!("labeled results must contain both correct and incorrect hits");
+        }
+
+        let mut use_distance = true;
+        let mut use_coverage = true;
+        let weights = loop {
+            let w = fit_logistic(samples, use_distance, use_coverage);
+            // A feature fitted against its expected direction is noise; drop it and refit.
+            if use_distance && w[1] > 0.0 {
+                use_distance = false;
+            } else if use_coverage && w[2] < 0.0 {
+                use_coverage = false;
+            } else {
+                break w;
+            }
+        };
+
+        Ok(Self {
+            method: METHOD.to_string(),
+            intercept: weights[0],
+            distance_weight: weights[1],
+            coverage_weight: weights[2],
+            samples: samples.len(),
+        })
+    }
+}
+
+/// Labels the top results of one query against its expected command.
+pub fn label_results(
+    query: &str,
+    expected_command: &str,
+    sorted: &[(String, CmdData)],
+    profile: &RankingProfile,
+) -> Vec<Sample> {
+    sorted
+        .iter()
+        .take(SAMPLES_PER_QUERY)
+        .map(|(cmd, data)| Sample {
+            adjusted_distance: data.adjusted_score,
+            coverage: search::evaluate_intent_coverage(query, cmd, data, profile).score,
+            correct: cmd == expected_command,
+        })
+        .collect()
+}
+
+/// The original uncalibrated curve: 1 - (distance / max)^7, kept high on purpose.
+pub fn curve_confidence(adjusted_distance: f64, max_distance: f64) -> f64 {
+    let ratio = (adjusted_distance / max_distance).clamp(0.0, 1.0);
+    1.0 - ratio.powf(7.0)
+}
+
+/// Per-user fits, next to `ranking.toml`: ~/.config/askman/calibration.json (linux).
+/// Kept out of `commands.db` so `askman update` does not discard them.
+pub fn store_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("askman").join(STORE_FILE))
+}
+
+/// A fit and the ranking settings it was computed under, stored per profile name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredFit {
+    settings: String,
+    calibration: Calibration,
+}
+
+/// What the store holds for the active profile.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stored {
+    Missing,
+    /// Fitted under different ranking settings; its distances no longer mean the same thing.
+    Stale,
+    Fitted(Calibration),
+}
+
+/// Fingerprint of the knobs that shape adjusted distances and coverage.
+/// `name` and `sources` are left out: moving a profile file does not change its ranking.
+pub fn settings_fingerprint(profile: &RankingProfile) -> String {
+    let settings = RankingProfile {
+        name: String::new(),
+        sources: vec![],
+        ..profile.clone()
+    };
+    // FNV-1a, so the fingerprint is stable across builds (unlike `DefaultHasher`).
+    let hash = serde_json::to_string(&settings)
+        .unwrap_or_default()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{hash:016x}")
+}
+
+/// Loads the fit stored for `profile`. A missing file, or an entry this build cannot read,
+/// is `Missing`, so callers fall back to `curve_confidence`.
+pub fn load(path: &Path, profile: &RankingProfile) -> Result<Stored> {
+    let Some(fit) = read_store(path)?.remove(&profile.name) else {
+        return Ok(Stored::Missing);
+    };
+    let Ok(fit) = serde_json::from_value::<StoredFit>(fit) else {
+        return Ok(Stored::Missing);
+    };
+    Ok(if fit.calibration.method != METHOD {
+        Stored::Missing
+    } else if fit.settings != settings_fingerprint(profile) {
+        Stored::Stale
+    } else {
+        Stored::Fitted(fit.calibration)
+    })
+}
+
+/// Stores `calibration` as the fit for `profile`, keeping other profiles' fits.
+/// A store that no longer parses is replaced, so recalibrating is always a way out.
+pub fn store(path: &Path, profile: &RankingProfile, calibration: &Calibration) -> Result<()> {
+    let mut fits = read_store(path).unwrap_or_default();
+    let fit = StoredFit {
+        settings: settings_fingerprint(profile),
+        calibration: calibration.clone(),
+    };
+    fits.insert(profile.name.clone(), serde_json::to_value(fit)?);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&fits)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn read_store(path: &Path) -> Result<BTreeMap<String, serde_json::Value>> {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    serde_json::from_str(&raw).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Regularized logistic regression over `[1, distance, coverage]`; disabled features stay at 0.
+fn fit_logistic(samples: &[Sample], use_distance: bool, use_coverage: bool) -> [f64; 3] {
+    let positives = samples.iter().filter(|s| s.correct).count() as f64;
+    let negatives = samples.len() as f64 - positives;
+    let target_hi = (positives + 1.0) / (positives + 2.0);
+    let target_lo = 1.0 / (negatives + 2.0);
+
+    let features = |s: &Sample| {
+        [
+            1.0,
+            if use_distance {
+                s.adjusted_distance
+            } else {
+                0.0
+            },
+            if use_coverage { s.coverage } else { 0.0 },
+        ]
+    };
+
+    let mut w = [0.0; 3];
+    for _ in 0..MAX_ITERATIONS {
+        let mut grad = [0.0; 3];
+        let mut hess = [[0.0; 3]; 3];
+        for s in samples {
+            let x = features(s);
+            let p = sigmoid(x[0] * w[0] + x[1] * w[1] + x[2] * w[2]);
+            let t = if s.correct { target_hi } else { target_lo };
+            for i in 0..3 {
+                grad[i] += (p - t) * x[i];
+                for j in 0..3 {
+                    hess[i][j] += p * (1.0 - p) * x[i] * x[j];
+                }
+            }
+        }
+        for i in 0..3 {
+            grad[i] += RIDGE * w[i];
+            hess[i][i] += RIDGE;
+        }
+
+        let Some(step) = solve3(hess, grad) else {
+            break;
+        };
+        for i in 0..3 {
+            w[i] -= step[i];
+        }
+        if step.iter().all(|d| d.abs() < 1e-9) {
+            break;
+        }
+    }
+    w
+}
+
+/// Gaussian elimination with partial pivoting for the 3x3 Newton system.
+fn solve3(mut a: [[f64; 3]; 3], mut b: [f64; 3]) -> Option<[f64; 3]> {
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..3 {
+            let factor = a[row][col] / pivot_row[col];
+            for (k, value) in a[row].iter_mut().enumerate().skip(col) {
+                *value -= factor * pivot_row[k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = [0.0; 3];
+    for row in (0..3).rev() {
+        let tail: f64 = (row + 1..3).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(adjusted_distance: f64, coverage: f64, correct: bool) -> Sample {
+        Sample {
+            adjusted_distance,
+            coverage,
+            correct,
+        }
+    }
+
+    fn labeled_set() -> Vec<Sample> {
+        vec![
+            sample(0.20, 1.0, true),
+            sample(0.30, 1.0, true),
+            sample(0.35, 0.5, true),
+            sample(0.40, 1.0, true),
+            sample(0.50, 0.5, false),
+            sample(0.45, 1.0, true),
+            sample(0.60, 0.5, false),
+            sample(0.70, 0.0, false),
+            sample(0.55, 1.0, true),
+            sample(0.80, 0.5, false),
+            sample(0.90, 0.0, false),
+            sample(0.65, 1.0, false),
+        ]
+    }
+
+    #[test]
+    fn fitted_mapping_is_monotonic() {
+        let c = Calibration::fit(&labeled_set()).unwrap();
+        assert_eq!(c.samples, 12);
+        assert!(c.distance_weight < 0.0);
+        assert!(c.coverage_weight >= 0.0);
+
+        assert!(c.probability(0.2, 1.0) > c.probability(0.6, 1.0));
+        assert!(c.probability(0.4, 1.0) >= c.probability(0.4, 0.0));
+        let p = c.probability(0.3, 1.0);
+        assert!(p > 0.5 && p < 1.0);
+    }
+
+    #[test]
+    fn rejects_tiny_or_one_sided_sets() {
+        assert!(Calibration::fit(&labeled_set()[..5]).is_err());
+        let all_correct: Vec<Sample> = (0..12)
+            .map(|i| sample(i as f64 / 10.0, 1.0, true))
+            .collect();
+        assert!(Calibration::fit(&all_correct).is_err());
+    }
+
+    #[test]
+    fn wrong_direction_feature_is_dropped() {
+        // Coverage anti-correlates with correctness here; it must not lower confidence.
+        let samples: Vec<Sample> = (0..12)
+            .map(|i| sample(0.1 + i as f64 * 0.05, if i < 6 { 0.0 } else { 1.0 }, i < 6))
+            .collect();
+        let c = Calibration::fit(&samples).unwrap();
+        assert_eq!(c.coverage_weight, 0.0);
+        assert!(c.distance_weight < 0.0);
+    }
+
+    #[test]
+    fn curve_matches_original_formula() {
+        assert_eq!(curve_confidence(0.0, 1.1), 1.0);
+        assert_eq!(curve_confidence(2.0, 1.1), 0.0);
+        assert!((curve_confidence(0.55, 1.1) - (1.0 - 0.5f64.powi(7))).abs() < 1e-12);
+    }
+
+    #[test]
+    fn stores_one_fit_per_profile_and_detects_changed_settings() {
+        let dir = std::env::temp_dir().join(format!("askman_calibration_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join(STORE_FILE);
+        let builtin = RankingProfile::default();
+        assert_eq!(load(&path, &builtin).unwrap(), Stored::Missing);
+
+        let c = Calibration::fit(&labeled_set()).unwrap();
+        store(&path, &builtin, &c).unwrap();
+        assert_eq!(load(&path, &builtin).unwrap(), Stored::Fitted(c.clone()));
+
+        let mut moved = builtin.clone();
+        moved.sources = vec![PathBuf::from("/etc/askman/ranking.toml")];
+        assert_eq!(load(&path, &moved).unwrap(), Stored::Fitted(c.clone()));
+
+        let mut tuned = builtin.clone();
+        tuned.weights.core_command = 0.5;
+        assert_eq!(load(&path, &tuned).unwrap(), Stored::Stale);
+
+        let mut other = tuned.clone();
+        other.name = "containers".to_string();
+        assert_eq!(load(&path, &other).unwrap(), Stored::Missing);
+        store(&path, &other, &c).unwrap();
+        assert_eq!(load(&path, &other).unwrap(), Stored::Fitted(c.clone()));
+        assert_eq!(load(&path, &builtin).unwrap(), Stored::Fitted(c.clone()));
+
+        std::fs::write(&path, r#"{"builtin": "not a fit"}"#).unwrap();
+        assert_eq!(load(&path, &builtin).unwrap(), Stored::Missing);
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load(&path, &builtin).is_err());
+        store(&path, &builtin, &c).unwrap();
+        assert_eq!(load(&path, &builtin).unwrap(), Stored::Fitted(c));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::explain::{self, Segment, TokenRole};
+use crate::risk::{self, Risk};
+use crate::search::Page;
+
+/// Verdict on a proposed command line, worst segment wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// Every command has a page and every flag appears in one of its examples.
+    Pass,
+    /// Known command, but some flag is not used by any stored example.
+    Warn,
+    /// No page for the command on the target OS.
+    Unknown,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// Where a flag was seen, or that it was not.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct FlagEvidence {
+    pub flag: String,
+    pub seen: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Syntax of the example that uses the flag.
+    pub example: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct CommandCheck {
+    pub operator: Option<String>,
+    pub text: String,
+    /// Page the command was identified as (`git-stash` for `git stash pop`).
+    pub command: Option<String>,
+    /// The tool itself (`git`).
+    pub family: Option<String>,
+    pub status: CheckStatus,
+    pub flags: Vec<FlagEvidence>,
+    /// Other platforms that have a page, when the target OS has none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub available_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckReport {
+    pub status: CheckStatus,
+    pub commands: Vec<CommandCheck>,
+    /// Risk of the line as a whole, by the same rules as returned examples.
+    pub risk: Risk,
+}
+
+/// Checks every command of `line` against its stored page. `lookup` reads a page for the
+/// target OS; `platforms` lists every OS that has a page of that name.
+pub fn check_line(
+    line: &str,
+    lookup: impl FnMut(&str) -> Result<Option<Page>>,
+    mut platforms: impl FnMut(&str) -> Result<Vec<String>>,
+) -> Result<CheckReport> {
+    let commands = explain::explain_line(line, lookup)?
+        .into_iter()
+        .map(|segment| check_segment(segment, &mut platforms))
+        .collect::<Result<Vec<_>>>()?;
+    let status = commands
+        .iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(CheckStatus::Unknown);
+    Ok(CheckReport {
+        status,
+        commands,
+        risk: risk::assess(line),
+    })
+}
+
+fn check_segment(
+    segment: Segment,
+    platforms: &mut impl FnMut(&str) -> Result<Vec<String>>,
+) -> Result<CommandCheck> {
+    let Segment {
+        operator,
+        text,
+        command,
+        tokens,
+        ..
+    } = segment;
+
+    let flags: Vec<FlagEvidence> = tokens
+        .iter()
+        .filter(|t| t.role == TokenRole::Flag)
+        .map(|t| FlagEvidence {
+            flag: t.token.clone(),
+            seen: t.explained,
+            aliases: t.aliases.clone(),
+            example: t.example.clone(),
+            description: t.explanation.clone(),
+        })
+        .collect();
+
+    let name = tokens.first().map(|t| {
+        t.token
+            .rsplit('/')
+            .next()
+            .unwrap_or(&t.token)
+            .to_lowercase()
+    });
+    let (status, family, available_on) = match (&command, &name) {
+        (Some(_), _) => {
+            let status = if flags.iter().all(|f| f.seen) {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Warn
+            };
+            (status, name, vec![])
+        }
+        (None, Some(name)) => (CheckStatus::Unknown, None, platforms(name)?),
+        (None, None) => (CheckStatus::Unknown, None, vec![]),
+    };
+
+    Ok(CommandCheck {
+        operator,
+        text,
+        command,
+        family,
+        status,
+        flags,
+        available_on,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskLevel;
+    use crate::search::Example;
+
+    fn page(command: &str, examples: &[(&str, &str)]) -> Page {
+        Page {
+            command: command.to_string(),
+            platform: "common".to_string(),
+            description: format!("{command} page."),
+            examples: examples
+                .iter()
+                .map(|(desc, syntax)| Example::new(*desc, *syntax))
+                .collect(),
+            variants: vec![],
+        }
+    }
+
+    fn check(line: &str) -> CheckReport {
+        let pages = [
+            page(
+                "rm",
+                &[
+                    ("Remove files", "rm {{path/to/file1 path/to/file2 ...}}"),
+                    (
+                        "Remove a directory recursively",
+                        "rm {{[-r|--recursive]}} {{path/to/directory}}",
+                    ),
+                ],
+            ),
+            page("git-stash", &[("List stashes", "git stash list")]),
+        ];
+        check_line(
+            line,
+            |name| Ok(pages.iter().find(|p| p.command == name).cloned()),
+            |name| {
+                Ok(match name {
+                    "pbcopy" => vec!["osx".to_string()],
+                    _ => vec![],
+                })
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn passes_when_every_flag_is_documented() {
+        let report = check("rm --recursive build && git stash list");
+        assert_eq!(report.status, CheckStatus::Pass);
+        let rm = &report.commands[0];
+        assert_eq!(rm.family.as_deref(), Some("rm"));
+        assert_eq!(rm.flags.len(), 1);
+        assert_eq!(rm.flags[0].aliases, vec!["-r", "--recursive"]);
+        assert_eq!(
+            rm.flags[0].example.as_deref(),
+            Some("rm {{[-r|--recursive]}} {{path/to/directory}}")
+        );
+        assert_eq!(report.commands[1].command.as_deref(), Some("git-stash"));
+        assert_eq!(report.commands[1].family.as_deref(), Some("git"));
+        assert_eq!(report.risk.level, RiskLevel::High);
+    }
+
+    #[test]
+    fn warns_on_unseen_flags_and_reports_unknown_commands() {
+        let report = check("rm -rv build");
+        assert_eq!(report.status, CheckStatus::Warn);
+        let flags: Vec<(&str, bool)> = report.commands[0]
+            .flags
+            .iter()
+            .map(|f| (f.flag.as_str(), f.seen))
+            .collect();
+        assert_eq!(flags, vec![("-r", true), ("-v", false)]);
+
+        let report = check("echo hi | pbcopy");
+        assert_eq!(report.status, CheckStatus::Unknown);
+        assert_eq!(report.commands[1].available_on, vec!["osx"]);
+        assert_eq!(report.commands[1].family, None);
+    }
+}
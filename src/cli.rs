@@ -1,9 +1,179 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Update askman binary and bundled commands database
     Update,
+    /// Download the optional cross-encoder reranker model used by --rerank
+    DownloadReranker,
+    /// Measure ranking quality against a JSONL file of expected answers
+    Eval(EvalArgs),
+    /// Print the full page for a known command without loading the embedding model
+    Show(ShowArgs),
+    /// Explain what each part of an existing command line does, from the stored examples
+    Explain(ExplainArgs),
+    /// Check a proposed command line against the stored examples before running it
+    Check(CheckArgs),
+    /// Serve search, show, explain and check as Model Context Protocol tools over stdio
+    Mcp(McpArgs),
+    /// Answer JSONL queries from stdin with one JSON result per line
+    Batch(BatchArgs),
+    /// Keep the embedding model and database loaded so searches skip the cold start
+    Daemon(DaemonArgs),
+    /// Print the JSON Schema of every --json output
+    Schema,
+    /// Fit confidence calibration from a JSONL file of expected answers and store it for the ranking profile
+    Calibrate(CalibrateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ShowArgs {
+    /// Command name; several words are joined with `-` (`git commit` -> `git-commit`)
+    #[arg(required = true)]
+    pub command: Vec<String>,
+
+    /// Output the page in JSON format
+    #[arg(long, short = 'j')]
+    pub json: bool,
+
+    /// Show the Linux page
+    #[arg(long, conflicts_with_all = ["osx", "windows"])]
+    pub linux: bool,
+
+    /// Show the macOS page
+    #[arg(long, conflicts_with_all = ["linux", "windows"])]
+    pub osx: bool,
+
+    /// Show the Windows page
+    #[arg(long, conflicts_with_all = ["linux", "osx"])]
+    pub windows: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExplainArgs {
+    /// The command line to explain; quote it so the shell passes pipes and `&&` through
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command_line: Vec<String>,
+
+    /// Output the explanation in JSON format
+    #[arg(long, short = 'j')]
+    pub json: bool,
+
+    /// Read Linux pages
+    #[arg(long, conflicts_with_all = ["osx", "windows"])]
+    pub linux: bool,
+
+    /// Read macOS pages
+    #[arg(long, conflicts_with_all = ["linux", "windows"])]
+    pub osx: bool,
+
+    /// Read Windows pages
+    #[arg(long, conflicts_with_all = ["linux", "osx"])]
+    pub windows: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CheckArgs {
+    /// The command line to check; quote it so the shell passes pipes and `&&` through
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command_line: Vec<String>,
+
+    /// Output the verdict and per-flag evidence in JSON format
+    #[arg(long, short = 'j')]
+    pub json: bool,
+
+    /// Check against Linux pages
+    #[arg(long, conflicts_with_all = ["osx", "windows"])]
+    pub linux: bool,
+
+    /// Check against macOS pages
+    #[arg(long, conflicts_with_all = ["linux", "windows"])]
+    pub osx: bool,
+
+    /// Check against Windows pages
+    #[arg(long, conflicts_with_all = ["linux", "osx"])]
+    pub windows: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct McpArgs {
+    /// Rescore search results with the cross-encoder reranker, loaded once for the session
+    #[arg(long)]
+    pub rerank: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BatchArgs {
+    /// Rescore the top results with the cross-encoder reranker
+    #[arg(long)]
+    pub rerank: bool,
+
+    /// Only return tools that are installed (resolvable on $PATH)
+    #[arg(long)]
+    pub installed_only: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DaemonArgs {
+    #[command(subcommand)]
+    pub action: Option<DaemonAction>,
+
+    /// Shut down after this many seconds without a query (0 keeps it running)
+    #[arg(long, value_name = "SECS", default_value_t = crate::daemon::DEFAULT_IDLE_TIMEOUT.as_secs())]
+    pub idle_timeout: u64,
+
+    /// Load the cross-encoder reranker up front instead of on the first --rerank query
+    #[arg(long)]
+    pub rerank: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonAction {
+    /// Report whether a daemon is running, and for how long
+    Status,
+    /// Stop the running daemon
+    Stop,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CalibrateArgs {
+    /// JSONL cases in the same format as `askman eval`
+    pub cases: PathBuf,
+
+    /// Print the fitted calibration without storing it
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct EvalArgs {
+    /// JSONL cases: {"query", "os"?, "expected_command", "expected_syntax"?}
+    pub cases: PathBuf,
+
+    /// Print the full report as JSON
+    #[arg(long, short = 'j')]
+    pub json: bool,
+
+    /// Rescore the top results with the cross-encoder reranker
+    #[arg(long)]
+    pub rerank: bool,
+
+    /// Fail if recall@1 is below this value (0.0-1.0)
+    #[arg(long, value_name = "FLOOR")]
+    pub min_recall_at_1: Option<f64>,
+
+    /// Fail if recall@3 is below this value (0.0-1.0)
+    #[arg(long, value_name = "FLOOR")]
+    pub min_recall_at_3: Option<f64>,
+
+    /// Fail if mean reciprocal rank is below this value (0.0-1.0)
+    #[arg(long, value_name = "FLOOR")]
+    pub min_mrr: Option<f64>,
+
+    /// Fail if the share of top results passing the intent gate is below this value (0.0-1.0)
+    #[arg(long, value_name = "FLOOR")]
+    pub min_intent_pass_rate: Option<f64>,
 }
 
 /// askman – offline CLI helper
@@ -37,6 +207,34 @@ pub struct Args {
     #[arg(long, short = 'j')]
     pub json: bool,
 
+    /// Rescore the top results with a cross-encoder reranker (model must be downloaded first)
+    #[arg(long)]
+    pub rerank: bool,
+
+    /// Only return tools that are installed (resolvable on $PATH)
+    #[arg(long)]
+    pub installed_only: bool,
+
+    /// Number of distinct commands to return (default: 2 with --json, 3 otherwise, noise-filtered)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: Option<u64>,
+
+    /// Skip this many distinct commands before returning results (for paging)
+    #[arg(long, value_name = "M", default_value_t = 0)]
+    pub offset: usize,
+
+    /// Hide examples riskier than this; commands left with no examples are dropped
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    pub max_risk: Option<crate::risk::RiskLevel>,
+
+    /// Only rank this tool and its subcommand pages, e.g. `--tool kubectl` (repeatable)
+    #[arg(long, value_name = "NAME")]
+    pub tool: Vec<String>,
+
+    /// Bind a placeholder in the top example, e.g. `--fill source_tar=backup.tar` (repeatable)
+    #[arg(long, value_name = "NAME=VALUE", value_parser = crate::placeholder::parse_binding)]
+    pub fill: Vec<(String, String)>,
+
     /// Force search for Linux commands
     #[arg(long, conflicts_with_all = ["osx", "windows"])]
     pub linux: bool,
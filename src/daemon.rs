@@ -0,0 +1,451 @@
+use anyhow::{Context, Result, bail};
+use colored::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::pipeline::{
+    DEFAULT_TEXT_RESULTS, Pipeline, RankOptions, ResultWindow, SearchOutcome, load_reranker,
+};
+use crate::{calibration, cli, db, pipeline, profile, search};
+
+/// The socket lives in its own directory, private to the user before the socket exists.
+const SOCKET_DIR: &str = "daemon";
+const SOCKET_FILE: &str = "daemon.sock";
+
+/// How long `askman daemon` waits for a query before shutting down.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// A forwarded query has this long to be answered before the client searches in-process,
+/// and a client has as long to send its request.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The daemon's socket. The data directory is per user, and so is the socket.
+pub fn socket_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(SOCKET_DIR).join(SOCKET_FILE)
+}
+
+/// One request per connection, written as a single JSON line. `Q` is the query payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+pub enum Request<Q> {
+    Status,
+    Stop,
+    Query(Q),
+}
+
+/// The answer to a `Request`, also a single JSON line. `A` is the answer payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+pub enum Response<A> {
+    Status(Status),
+    Stopping,
+    Answer(A),
+    /// The query could not be answered; the client falls back to searching in-process.
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    pub version: String,
+    pub socket: PathBuf,
+    pub uptime_secs: u64,
+    /// Seconds since the last query.
+    pub idle_secs: u64,
+    /// `None` when the daemon never times out.
+    pub idle_timeout_secs: Option<u64>,
+    pub queries: u64,
+}
+
+/// Sends one request to the daemon. `Ok(None)` means no daemon is listening.
+#[cfg(unix)]
+pub fn call<Q: Serialize, A: DeserializeOwned>(
+    socket: &Path,
+    request: &Request<Q>,
+) -> Result<Option<Response<A>>> {
+    use std::io::ErrorKind;
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = match UnixStream::connect(socket) {
+        Ok(stream) => stream,
+        // A socket file left by a daemon that died refuses connections.
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            return Ok(None);
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to connect to {}", socket.display()));
+        }
+    };
+    stream.set_read_timeout(Some(ANSWER_TIMEOUT))?;
+    write_message(&mut stream, request)?;
+    read_message(&stream).map(Some)
+}
+
+#[cfg(not(unix))]
+pub fn call<Q: Serialize, A: DeserializeOwned>(
+    _socket: &Path,
+    _request: &Request<Q>,
+) -> Result<Option<Response<A>>> {
+    Ok(None)
+}
+
+/// Answers requests on `socket` until asked to stop or idle for `idle_timeout`
+/// (`None` never times out). Queries are answered one at a time by `answer`.
+#[cfg(unix)]
+pub fn serve<Q: DeserializeOwned, A: Serialize>(
+    socket: &Path,
+    idle_timeout: Option<Duration>,
+    mut answer: impl FnMut(Q) -> Result<A>,
+) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    // `bind` creates the socket with the umask's permissions; other users must not be able to
+    // reach it in the meantime, so its directory is closed to them first.
+    if let Some(dir) = socket.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("a daemon is already listening on {}", socket.display());
+        }
+        std::fs::remove_file(socket)
+            .with_context(|| format!("failed to remove stale socket {}", socket.display()))?;
+    }
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("failed to listen on {}", socket.display()))?;
+    let _remove_socket = RemoveOnDrop(socket.to_path_buf());
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))?;
+
+    let started = Instant::now();
+    let last_query = Arc::new(Mutex::new(Instant::now()));
+    let expired = Arc::new(AtomicBool::new(false));
+    if let Some(timeout) = idle_timeout {
+        let (socket, last_query, expired) =
+            (socket.to_path_buf(), last_query.clone(), expired.clone());
+        std::thread::spawn(move || {
+            loop {
+                let idle = last_query.lock().map(|t| t.elapsed()).unwrap_or(timeout);
+                if idle >= timeout {
+                    expired.store(true, Ordering::SeqCst);
+                    // Wake the accept loop so it sees the flag.
+                    let _ = UnixStream::connect(&socket);
+                    return;
+                }
+                std::thread::sleep(timeout - idle);
+            }
+        });
+    }
+
+    let touch = || {
+        if let Ok(mut t) = last_query.lock() {
+            *t = Instant::now();
+        }
+    };
+    let mut queries = 0;
+    for stream in listener.incoming() {
+        if expired.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(mut stream) = stream else { continue };
+        // A client that connects and never writes must not wedge the loop or the idle timeout.
+        if stream.set_read_timeout(Some(ANSWER_TIMEOUT)).is_err() {
+            continue;
+        }
+
+        let (response, stop) = match read_message::<Request<Q>>(&stream) {
+            Ok(Request::Status) => {
+                let status = Status {
+                    pid: std::process::id(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    socket: socket.to_path_buf(),
+                    uptime_secs: started.elapsed().as_secs(),
+                    idle_secs: last_query.lock().map_or(0, |t| t.elapsed().as_secs()),
+                    idle_timeout_secs: idle_timeout.map(|t| t.as_secs()),
+                    queries,
+                };
+                (Response::Status(status), false)
+            }
+            Ok(Request::Stop) => (Response::Stopping, true),
+            Ok(Request::Query(query)) => {
+                touch();
+                queries += 1;
+                let response = match answer(query) {
+                    Ok(answer) => Response::Answer(answer),
+                    Err(e) => Response::Error(format!("{e:#}")),
+                };
+                touch();
+                (response, false)
+            }
+            Err(e) => (Response::Error(format!("{e:#}")), false),
+        };
+        // The client may have given up waiting; that is its business.
+        let _ = write_message(&mut stream, &response);
+        if stop {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve<Q: DeserializeOwned, A: Serialize>(
+    _socket: &Path,
+    _idle_timeout: Option<Duration>,
+    _answer: impl FnMut(Q) -> Result<A>,
+) -> Result<()> {
+    bail!("askman daemon needs Unix domain sockets")
+}
+
+/// A search forwarded to `askman daemon`.
+#[derive(Serialize, Deserialize)]
+pub struct DaemonQuery {
+    /// The daemon only answers clients of its own build and ranking profile.
+    pub version: String,
+    pub profile: profile::RankingProfile,
+    pub query: String,
+    pub os: String,
+    pub options: RankOptions,
+    pub tools: Vec<String>,
+    pub rerank: bool,
+    /// The client's `$PATH`; `installed` is about the caller's environment, not the daemon's.
+    pub path: Option<std::ffi::OsString>,
+}
+
+/// Forwards a search to a running `askman daemon`. `None` (no daemon, or one that cannot
+/// answer this query) means search in-process.
+pub fn ask_daemon(app_dir: &Path, query: &DaemonQuery, verbose: bool) -> Option<SearchOutcome> {
+    let socket = socket_path(app_dir);
+    let reason = match call(&socket, &Request::Query(query)) {
+        Ok(None) => return None,
+        Ok(Some(Response::Answer(outcome))) => return Some(outcome),
+        Ok(Some(Response::Error(message))) => message,
+        Ok(Some(_)) => "unexpected response".to_string(),
+        Err(e) => format!("{e:#}"),
+    };
+    if verbose {
+        eprintln!(
+            "{}",
+            format!("(Daemon skipped: {reason}; searching in-process)").bright_black()
+        );
+    }
+    None
+}
+
+/// Runs the search daemon in the foreground, or asks a running one for its status or to stop.
+pub fn run_daemon(daemon_args: &cli::DaemonArgs) -> Result<()> {
+    let app_dir = db::get_app_dir()?;
+    let socket = socket_path(&app_dir);
+
+    let request = match daemon_args.action {
+        None => return serve_daemon(daemon_args, &app_dir, &socket),
+        Some(cli::DaemonAction::Status) => Request::Status,
+        Some(cli::DaemonAction::Stop) => Request::Stop,
+    };
+    match call::<DaemonQuery, SearchOutcome>(&socket, &request)? {
+        None => {
+            println!("askman daemon is not running");
+            if matches!(request, Request::Status) {
+                std::process::exit(1);
+            }
+        }
+        Some(Response::Status(status)) => {
+            let idle_timeout = status
+                .idle_timeout_secs
+                .map_or("never".to_string(), |secs| format!("after {secs}s idle"));
+            println!(
+                "askman daemon {} running (pid {})",
+                status.version, status.pid
+            );
+            println!("  socket    {}", status.socket.display());
+            println!("  uptime    {}s", status.uptime_secs);
+            println!("  queries   {}", status.queries);
+            println!("  idle      {}s (stops {idle_timeout})", status.idle_secs);
+        }
+        Some(Response::Stopping) => println!("askman daemon stopped"),
+        Some(Response::Error(message)) => bail!("daemon error: {message}"),
+        Some(Response::Answer(_)) => bail!("unexpected daemon response"),
+    }
+    Ok(())
+}
+
+/// Holds the connection, profile and models until stopped or idle; the CLI forwards
+/// searches here while it runs.
+fn serve_daemon(daemon_args: &cli::DaemonArgs, app_dir: &Path, socket: &Path) -> Result<()> {
+    let conn = db::get_connection(&db::get_db_path(app_dir)?)?;
+    let profile = profile::load_profile()?;
+    let options = RankOptions {
+        json: false,
+        installed_only: false,
+        depth: ResultWindow::default().depth(DEFAULT_TEXT_RESULTS),
+        max_risk: None,
+    };
+    let mut pipeline = Pipeline::load(
+        &conn,
+        app_dir,
+        &profile,
+        daemon_args.rerank || profile.rerank.enabled,
+        options,
+    )?;
+    let idle_timeout = Some(Duration::from_secs(daemon_args.idle_timeout)).filter(|t| !t.is_zero());
+
+    eprintln!("askman daemon listening on {}", socket.display());
+    let mut calibrated = calibration_mtime();
+    serve(socket, idle_timeout, |query: DaemonQuery| {
+        // `askman calibrate` may have refitted while the daemon ran.
+        let mtime = calibration_mtime();
+        if mtime != calibrated {
+            pipeline.calibration = pipeline::load_calibration(pipeline.profile);
+            calibrated = mtime;
+        }
+        answer_daemon_query(&mut pipeline, app_dir, query)
+    })
+}
+
+/// When the calibration store was last written; `None` while there is none.
+fn calibration_mtime() -> Option<std::time::SystemTime> {
+    let path = calibration::store_path()?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Ranks a forwarded query exactly as the client would have in-process.
+fn answer_daemon_query(
+    pipeline: &mut Pipeline,
+    app_dir: &Path,
+    query: DaemonQuery,
+) -> Result<SearchOutcome> {
+    if query.version != env!("CARGO_PKG_VERSION") {
+        bail!(
+            "daemon runs askman {}, client is {}",
+            env!("CARGO_PKG_VERSION"),
+            query.version
+        );
+    }
+    if query.profile != *pipeline.profile {
+        bail!("ranking profile changed since the daemon started");
+    }
+    let target_os = search::TargetOs::from_name(&query.os)
+        .ok_or_else(|| anyhow::anyhow!("unknown os `{}`", query.os))?;
+
+    pipeline.scope = (!query.tools.is_empty())
+        .then(|| search::resolve_tool_scope(&query.tools, &pipeline.commands, &pipeline.families));
+    pipeline.options = query.options;
+    // The reranker loads on the first query that asks for it, then stays loaded.
+    if query.rerank && pipeline.reranker.is_none() {
+        (pipeline.reranker, pipeline.rerank_skipped) = load_reranker(app_dir, true);
+    }
+    pipeline.rerank = query.rerank;
+    // A client without `$PATH` has only shell builtins, as `installed::is_installed` assumes.
+    pipeline.path = Some(query.path.unwrap_or_default());
+    pipeline.search(&query.query, target_os)
+}
+
+/// Removes the socket when the daemon exits, however it exits.
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn write_message(mut writer: impl Write, message: &impl Serialize) -> Result<()> {
+    serde_json::to_writer(&mut writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_message<T: DeserializeOwned>(reader: impl Read) -> Result<T> {
+    let mut line = String::new();
+    if BufReader::new(reader).read_line(&mut line)? == 0 {
+        bail!("connection closed before a message arrived");
+    }
+    serde_json::from_str(&line).context("malformed daemon message")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn temp_socket(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("askman-daemon-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        socket_path(&dir)
+    }
+
+    #[test]
+    fn answers_queries_until_stopped() {
+        let socket = temp_socket("stop");
+        assert_eq!(
+            call::<String, String>(&socket, &Request::Status).unwrap(),
+            None
+        );
+
+        let server = {
+            let socket = socket.clone();
+            std::thread::spawn(move || {
+                serve(&socket, None, |query: String| {
+                    if query.is_empty() {
+                        bail!("empty query");
+                    }
+                    Ok(query.to_uppercase())
+                })
+            })
+        };
+        while !socket.exists() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let ask = |request: Request<String>| call::<String, String>(&socket, &request).unwrap();
+        assert_eq!(
+            ask(Request::Query("tar".to_string())),
+            Some(Response::Answer("TAR".to_string()))
+        );
+        assert_eq!(
+            ask(Request::Query(String::new())),
+            Some(Response::Error("empty query".to_string()))
+        );
+        match ask(Request::Status) {
+            Some(Response::Status(status)) => {
+                assert_eq!(status.queries, 2);
+                assert_eq!(status.idle_timeout_secs, None);
+            }
+            other => panic!("expected status, got {other:?}"),
+        }
+        assert_eq!(ask(Request::Stop), Some(Response::Stopping));
+
+        server.join().unwrap().unwrap();
+        assert!(!socket.exists());
+        assert_eq!(ask(Request::Status), None);
+    }
+
+    #[test]
+    fn shuts_down_when_idle_and_replaces_stale_sockets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let socket = temp_socket("idle");
+        let dir = socket.parent().unwrap();
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // Left behind by a daemon that was killed.
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        assert!(socket.exists());
+
+        serve(&socket, Some(Duration::from_millis(50)), |q: String| Ok(q)).unwrap();
+        assert!(!socket.exists());
+        let mode = std::fs::metadata(dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
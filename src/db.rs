@@ -1,6 +1,6 @@
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
-use rusqlite::Connection;
+use rusqlite::{Connection, params};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
@@ -23,6 +23,7 @@ pub fn get_app_dir() -> Result<PathBuf> {
 }
 
 /// Resolves commands.db path. Falls back to downloading from GitHub on first run.
+#[allow(clippy::collapsible_if)]
 pub fn get_db_path(app_dir: &Path) -> Result<PathBuf> {
     // Check next to executable first (backward compat for local dev installs)
     if let Ok(exe_path) = std::env::current_exe() {
@@ -41,7 +42,7 @@ pub fn get_db_path(app_dir: &Path) -> Result<PathBuf> {
     }
 
     if !global_db_path.exists() {
-        println!("Downloading initial commands database (this only happens once)...");
+        eprintln!("Downloading initial commands database (this only happens once)...");
 
         let client = reqwest::blocking::Client::builder()
             .timeout(std::time::Duration::from_secs(120))
@@ -99,6 +100,15 @@ pub fn get_connection(db_path: &Path) -> Result<Connection> {
     Ok(Connection::open(db_path)?)
 }
 
+pub fn has_table(conn: &Connection, name: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
 /// Checks if the database has the required schema (must have the `os` metadata column).
 /// If it's an old v1 schema (missing `os`), it actively removes it so it can be rebuilt.
 pub fn ensure_valid_schema(db_path: &Path) -> Result<()> {
@@ -122,7 +132,7 @@ pub fn ensure_valid_schema(db_path: &Path) -> Result<()> {
     };
 
     if !has_os_column {
-        println!(
+        eprintln!(
             "Detected legacy database schema (v1, missing OS flags). Removing to allow upgrade..."
         );
         std::fs::remove_file(db_path)?;
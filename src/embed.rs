@@ -19,3 +19,11 @@ pub fn embed_query(embedder: &TextEmbedding, query: &str) -> Result<Vec<f32>> {
     let q_vec = embedder.embed(vec![query], None)?[0].clone();
     Ok(q_vec)
 }
+
+/// Embeds several queries in one model call, in order.
+pub fn embed_queries(embedder: &TextEmbedding, queries: &[&str]) -> Result<Vec<Vec<f32>>> {
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+    embedder.embed(queries.to_vec(), None)
+}
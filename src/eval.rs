@@ -0,0 +1,360 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::profile::RankingProfile;
+use crate::search::{self, CmdData, TargetOs};
+
+/// One line of an eval file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    pub query: String,
+    /// tldr platform name (`linux`, `osx`, ...); defaults to the host OS.
+    #[serde(default)]
+    pub os: Option<String>,
+    pub expected_command: String,
+    /// Substring the expected command's best example must contain (whitespace-insensitive).
+    #[serde(default)]
+    pub expected_syntax: Option<String>,
+}
+
+impl EvalCase {
+    pub fn target_os(&self, default: TargetOs) -> Result<TargetOs> {
+        match &self.os {
+            None => Ok(default),
+            Some(name) => TargetOs::from_name(name)
+                .with_context(|| format!("unknown os `{name}` in case `{}`", self.query)),
+        }
+    }
+}
+
+/// Reads a JSONL eval file. Blank lines and `#` comments are skipped.
+pub fn load_cases(path: &Path) -> Result<Vec<EvalCase>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read eval cases {}", path.display()))?;
+    parse_cases(&content).with_context(|| format!("invalid eval cases {}", path.display()))
+}
+
+pub fn parse_cases(content: &str) -> Result<Vec<EvalCase>> {
+    let mut cases = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let case: EvalCase =
+            serde_json::from_str(line).with_context(|| format!("line {}", i + 1))?;
+        cases.push(case);
+    }
+    if cases.is_empty() {
+        bail!("no eval cases found");
+    }
+    Ok(cases)
+}
+
+/// Outcome of a single case against the ranked results.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub query: String,
+    pub os: String,
+    pub expected_command: String,
+    /// 1-based rank of `expected_command`, `None` when it was not returned.
+    pub rank: Option<usize>,
+    /// Top commands actually returned, best first.
+    pub top: Vec<String>,
+    pub intent_pass: bool,
+    pub intent_missing_terms: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_syntax: Option<String>,
+    /// Whether the top result's best example contains `expected_syntax`; `None` when not asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub syntax_hit: Option<bool>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.rank == Some(1) && self.syntax_hit != Some(false)
+    }
+}
+
+/// Number of returned commands listed in per-case diffs.
+pub const TOP_SHOWN: usize = 3;
+
+/// Scores one case. `sorted` is the output of the full ranking pipeline for `case.query`,
+/// and `ranked_query` the query it actually ranked (after typo correction).
+pub fn score_case(
+    case: &EvalCase,
+    target_os: TargetOs,
+    ranked_query: &str,
+    sorted: &[(String, CmdData)],
+    profile: &RankingProfile,
+) -> CaseResult {
+    let rank = sorted
+        .iter()
+        .position(|(cmd, _)| cmd == &case.expected_command)
+        .map(|i| i + 1);
+
+    let (intent_pass, intent_missing_terms) = match sorted.first() {
+        Some((cmd, data)) => {
+            let intent = search::evaluate_intent_coverage(ranked_query, cmd, data, profile);
+            (intent.strong, intent.missing_terms)
+        }
+        None => (false, vec![]),
+    };
+
+    let syntax_hit = case.expected_syntax.as_ref().map(|expected| {
+        let expected = normalize_whitespace(expected);
+        sorted.first().is_some_and(|(cmd, data)| {
+            cmd == &case.expected_command
+                && data
+                    .examples
+                    .first()
+                    .is_some_and(|e| normalize_whitespace(&e.syntax).contains(&expected))
+        })
+    });
+
+    CaseResult {
+        query: case.query.clone(),
+        os: target_os.as_str().to_string(),
+        expected_command: case.expected_command.clone(),
+        rank,
+        top: sorted
+            .iter()
+            .take(TOP_SHOWN)
+            .map(|(cmd, _)| cmd.clone())
+            .collect(),
+        intent_pass,
+        intent_missing_terms,
+        expected_syntax: case.expected_syntax.clone(),
+        syntax_hit,
+    }
+}
+
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metrics {
+    pub cases: usize,
+    pub recall_at_1: f64,
+    pub recall_at_3: f64,
+    /// Mean reciprocal rank; cases whose command is missing count as 0.
+    pub mrr: f64,
+    pub intent_pass_rate: f64,
+    /// Over cases with `expected_syntax` only; `None` when no case sets it.
+    pub syntax_hit_rate: Option<f64>,
+}
+
+pub fn summarize(results: &[CaseResult]) -> Metrics {
+    let n = results.len().max(1) as f64;
+    let rate = |count: usize| count as f64 / n;
+
+    let syntax: Vec<bool> = results.iter().filter_map(|r| r.syntax_hit).collect();
+    let syntax_hit_rate = if syntax.is_empty() {
+        None
+    } else {
+        Some(syntax.iter().filter(|hit| **hit).count() as f64 / syntax.len() as f64)
+    };
+
+    Metrics {
+        cases: results.len(),
+        recall_at_1: rate(results.iter().filter(|r| r.rank == Some(1)).count()),
+        recall_at_3: rate(
+            results
+                .iter()
+                .filter(|r| r.rank.is_some_and(|k| k <= 3))
+                .count(),
+        ),
+        mrr: results
+            .iter()
+            .map(|r| r.rank.map_or(0.0, |k| 1.0 / k as f64))
+            .sum::<f64>()
+            / n,
+        intent_pass_rate: rate(results.iter().filter(|r| r.intent_pass).count()),
+        syntax_hit_rate,
+    }
+}
+
+/// Minimum acceptable metrics; unset floors are not enforced.
+#[derive(Debug, Clone, Default)]
+pub struct Floors {
+    pub recall_at_1: Option<f64>,
+    pub recall_at_3: Option<f64>,
+    pub mrr: Option<f64>,
+    pub intent_pass_rate: Option<f64>,
+}
+
+/// Describes every metric that fell below its floor.
+pub fn check_floors(metrics: &Metrics, floors: &Floors) -> Vec<String> {
+    [
+        ("recall@1", metrics.recall_at_1, floors.recall_at_1),
+        ("recall@3", metrics.recall_at_3, floors.recall_at_3),
+        ("mrr", metrics.mrr, floors.mrr),
+        (
+            "intent_pass_rate",
+            metrics.intent_pass_rate,
+            floors.intent_pass_rate,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, value, floor)| {
+        let floor = floor?;
+        (value < floor).then(|| format!("{name} {value:.4} < {floor:.4}"))
+    })
+    .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalReport {
+    pub metrics: Metrics,
+    /// Floor violations; non-empty means the run failed.
+    pub failures: Vec<String>,
+    pub cases: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    pub fn new(cases: Vec<CaseResult>, floors: &Floors) -> Self {
+        let metrics = summarize(&cases);
+        let failures = check_floors(&metrics, floors);
+        Self {
+            metrics,
+            failures,
+            cases,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Example;
+
+    fn result(cmd: &str, syntax: &str) -> (String, CmdData) {
+        (
+            cmd.to_string(),
+            CmdData::fixture(
+                &format!("{cmd} description."),
+                vec![Example::new("Example", syntax)],
+                0.5,
+            ),
+        )
+    }
+
+    fn case(query: &str, expected: &str) -> EvalCase {
+        EvalCase {
+            query: query.to_string(),
+            os: None,
+            expected_command: expected.to_string(),
+            expected_syntax: None,
+        }
+    }
+
+    #[test]
+    fn parses_jsonl_skipping_comments() {
+        let cases = parse_cases(
+            r#"
+            # archive handling
+            {"query": "extract tar archive", "expected_command": "tar"}
+            {"query": "list files", "os": "osx", "expected_command": "ls", "expected_syntax": "ls -la"}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[1].target_os(TargetOs::Linux).unwrap(), TargetOs::Osx);
+        assert_eq!(
+            cases[0].target_os(TargetOs::Linux).unwrap(),
+            TargetOs::Linux
+        );
+
+        assert!(parse_cases("{\"query\": \"x\"}").is_err());
+        assert!(parse_cases("\n# nothing\n").is_err());
+    }
+
+    #[test]
+    fn scores_rank_and_syntax() {
+        let sorted = vec![
+            result("tar", "tar xf {{path/to/source.tar}}"),
+            result("unzip", "unzip {{path/to/archive.zip}}"),
+        ];
+        let mut c = case("extract tar archive", "tar");
+        c.expected_syntax = Some("tar  xf".to_string());
+        let r = score_case(
+            &c,
+            TargetOs::Linux,
+            &c.query,
+            &sorted,
+            &RankingProfile::default(),
+        );
+        assert_eq!(r.rank, Some(1));
+        assert_eq!(r.syntax_hit, Some(true));
+        assert_eq!(r.top, vec!["tar", "unzip"]);
+        assert!(r.passed());
+
+        let r = score_case(
+            &case("unzip a file", "unzip"),
+            TargetOs::Linux,
+            "unzip a file",
+            &sorted,
+            &RankingProfile::default(),
+        );
+        assert_eq!(r.rank, Some(2));
+        assert!(!r.passed());
+
+        let r = score_case(
+            &case("zip it", "zip"),
+            TargetOs::Linux,
+            "zip it",
+            &[],
+            &RankingProfile::default(),
+        );
+        assert_eq!(r.rank, None);
+        assert!(!r.intent_pass);
+    }
+
+    fn ranked(rank: Option<usize>, intent_pass: bool) -> CaseResult {
+        CaseResult {
+            query: String::new(),
+            os: "linux".to_string(),
+            expected_command: String::new(),
+            rank,
+            top: vec![],
+            intent_pass,
+            intent_missing_terms: vec![],
+            expected_syntax: None,
+            syntax_hit: None,
+        }
+    }
+
+    #[test]
+    fn summarizes_recall_and_mrr() {
+        let results = vec![
+            ranked(Some(1), true),
+            ranked(Some(2), true),
+            ranked(Some(4), false),
+            ranked(None, false),
+        ];
+        let m = summarize(&results);
+        assert_eq!(m.cases, 4);
+        assert_eq!(m.recall_at_1, 0.25);
+        assert_eq!(m.recall_at_3, 0.5);
+        assert!((m.mrr - (1.0 + 0.5 + 0.25) / 4.0).abs() < 1e-9);
+        assert_eq!(m.intent_pass_rate, 0.5);
+        assert_eq!(m.syntax_hit_rate, None);
+    }
+
+    #[test]
+    fn reports_metrics_below_floors() {
+        let report = EvalReport::new(
+            vec![ranked(Some(2), true)],
+            &Floors {
+                recall_at_1: Some(0.8),
+                recall_at_3: Some(0.8),
+                mrr: None,
+                intent_pass_rate: Some(1.0),
+            },
+        );
+        assert_eq!(report.failures, vec!["recall@1 0.0000 < 0.8000"]);
+    }
+}
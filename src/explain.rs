@@ -0,0 +1,703 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::format::{CommandToken, clean_description, tokenize_command};
+use crate::search::{Example, Page};
+
+/// Operators that end one command and start the next, longest first.
+const OPERATORS: &[&str] = &["||", "&&", "|&", "|", ";"];
+
+/// What a word of the command line is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenRole {
+    Command,
+    /// Second word of a `tool-sub` page (`stash` in `git stash pop`).
+    Subcommand,
+    Flag,
+    /// Argument taken by the preceding flag (`/tmp` in `-C /tmp`).
+    Value,
+    Argument,
+}
+
+/// One word of a segment and where its meaning came from.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct TokenExplanation {
+    pub token: String,
+    pub role: TokenRole,
+    /// False when no example of the page uses this token; do not guess what it does.
+    pub explained: bool,
+    /// Description of the example the token was found in (the page description for the command).
+    pub explanation: Option<String>,
+    /// Syntax of that example.
+    pub example: Option<String>,
+    /// Interchangeable spellings of a flag (`-f`, `--force`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// The `{{...}}` placeholder a value or argument stands in for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+}
+
+impl TokenExplanation {
+    fn unexplained(token: &str, role: TokenRole) -> Self {
+        Self {
+            token: token.to_string(),
+            role,
+            explained: false,
+            explanation: None,
+            example: None,
+            aliases: vec![],
+            placeholder: None,
+        }
+    }
+
+    fn from_example(token: &str, role: TokenRole, example: &Example) -> Self {
+        Self {
+            explained: true,
+            explanation: Some(example.description.replace(['[', ']'], "")),
+            example: Some(example.syntax.clone()),
+            ..Self::unexplained(token, role)
+        }
+    }
+}
+
+/// One command of a pipeline or `&&` / `||` / `;` chain.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Segment {
+    /// Operator joining this segment to the previous one; `None` for the first.
+    pub operator: Option<String>,
+    pub text: String,
+    /// Page the segment was identified as; `None` when the database has no page for it.
+    pub command: Option<String>,
+    pub description: Option<String>,
+    pub tokens: Vec<TokenExplanation>,
+}
+
+impl Segment {
+    pub fn unexplained(&self) -> impl Iterator<Item = &str> {
+        self.tokens
+            .iter()
+            .filter(|t| !t.explained)
+            .map(|t| t.token.as_str())
+    }
+}
+
+/// Explains every segment of `line`. `lookup` returns the page for a command name, as
+/// `search::lookup_page` does; `git stash pop` is read from `git-stash` when that page exists.
+pub fn explain_line(
+    line: &str,
+    mut lookup: impl FnMut(&str) -> Result<Option<Page>>,
+) -> Result<Vec<Segment>> {
+    split_segments(line)
+        .into_iter()
+        .map(|(operator, text)| explain_segment(operator, text, &mut lookup))
+        .collect()
+}
+
+fn explain_segment(
+    operator: Option<&str>,
+    text: String,
+    lookup: &mut impl FnMut(&str) -> Result<Option<Page>>,
+) -> Result<Segment> {
+    let words = shell_words(&text);
+    let name = words
+        .first()
+        .map(|w| w.rsplit('/').next().unwrap_or(w).to_lowercase())
+        .unwrap_or_default();
+
+    let mut page = None;
+    let mut command_words = 1;
+    if let Some(sub) = words.get(1).filter(|w| !w.starts_with('-')) {
+        page = lookup(&format!("{name}-{}", sub.to_lowercase()))?;
+        if page.is_some() {
+            command_words = 2;
+        }
+    }
+    if page.is_none() {
+        page = lookup(&name)?;
+    }
+
+    let Some(page) = page else {
+        let tokens = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| TokenExplanation::unexplained(w, shape_role(i, w)))
+            .collect();
+        return Ok(Segment {
+            operator: operator.map(str::to_string),
+            text,
+            command: None,
+            description: None,
+            tokens,
+        });
+    };
+
+    let description = clean_description(&page.description)
+        .trim_end()
+        .replace(['[', ']'], "");
+    let mut tokens = vec![TokenExplanation {
+        explained: true,
+        explanation: Some(description.clone()),
+        ..TokenExplanation::unexplained(&words[0], TokenRole::Command)
+    }];
+    if command_words == 2 {
+        tokens.push(TokenExplanation {
+            explained: true,
+            explanation: Some(description.clone()),
+            ..TokenExplanation::unexplained(&words[1], TokenRole::Subcommand)
+        });
+    }
+
+    let examples: Vec<ExampleSyntax> = page
+        .examples
+        .iter()
+        .map(|e| parse_example(&e.syntax, command_words))
+        .collect();
+    tokens.extend(explain_arguments(
+        &words[command_words..],
+        &page.examples,
+        &examples,
+    ));
+
+    Ok(Segment {
+        operator: operator.map(str::to_string),
+        text,
+        command: Some(page.command),
+        description: Some(description),
+        tokens,
+    })
+}
+
+fn shape_role(index: usize, word: &str) -> TokenRole {
+    if index == 0 {
+        TokenRole::Command
+    } else if is_flag(word) {
+        TokenRole::Flag
+    } else {
+        TokenRole::Argument
+    }
+}
+
+fn explain_arguments(
+    words: &[String],
+    examples: &[Example],
+    parsed: &[ExampleSyntax],
+) -> Vec<TokenExplanation> {
+    // Positional arguments are matched against the example that shares the most words with the line.
+    let best = (0..parsed.len()).max_by_key(|&i| {
+        let shared = words
+            .iter()
+            .filter(|w| parsed[i].mentions(flag_name(w)))
+            .count();
+        (shared, std::cmp::Reverse(i))
+    });
+    let mut slots = best.map(|i| parsed[i].slots.iter()).into_iter().flatten();
+    let mut last_slot: Option<&String> = None;
+
+    let mut tokens = Vec::new();
+    let mut pending_value: Option<(usize, String)> = None;
+    for word in words {
+        if let Some((i, placeholder)) = pending_value.take() {
+            tokens.push(TokenExplanation {
+                placeholder: Some(placeholder),
+                ..TokenExplanation::from_example(word, TokenRole::Value, &examples[i])
+            });
+            continue;
+        }
+
+        if is_flag(word) {
+            let (explained, value) = explain_flag(word, examples, parsed);
+            pending_value = value;
+            tokens.extend(explained);
+            continue;
+        }
+
+        if let Some(i) = parsed.iter().position(|p| p.literals.contains(word)) {
+            tokens.push(TokenExplanation::from_example(
+                word,
+                TokenRole::Argument,
+                &examples[i],
+            ));
+            continue;
+        }
+
+        // `{{path/to/file1 path/to/file2 ...}}` takes every remaining argument.
+        let slot = slots.next().or(last_slot.filter(|s| s.contains("...")));
+        match (slot, best) {
+            (Some(slot), Some(i)) => {
+                last_slot = Some(slot);
+                tokens.push(TokenExplanation {
+                    placeholder: Some(slot.clone()),
+                    ..TokenExplanation::from_example(word, TokenRole::Argument, &examples[i])
+                });
+            }
+            _ => tokens.push(TokenExplanation::unexplained(word, TokenRole::Argument)),
+        }
+    }
+    tokens
+}
+
+/// Explains a flag, splitting bundled short options (`-xzvf`) and inline values (`-C/tmp`,
+/// `--file=x`) when the whole word is not documented. Also returns the example and placeholder
+/// of the value the flag consumes from the next word, if it takes one.
+fn explain_flag(
+    word: &str,
+    examples: &[Example],
+    parsed: &[ExampleSyntax],
+) -> (Vec<TokenExplanation>, Option<(usize, String)>) {
+    let name = flag_name(word);
+    let inline_value = name.len() < word.len();
+    if let Some(found) = find_flag(parsed, name) {
+        let value = (!inline_value).then(|| takes_value(parsed, name)).flatten();
+        let token = flag_token(word, &found, examples);
+        return (vec![token], value.map(|v| (found.example, v)));
+    }
+
+    let single_dash = word.starts_with('-') && !word.starts_with("--");
+    if !single_dash || word.len() <= 2 {
+        return (
+            vec![TokenExplanation::unexplained(word, TokenRole::Flag)],
+            None,
+        );
+    }
+
+    // `-C/tmp`: a short option that takes a value, written without a space. A tail of
+    // plain letters (`-rv`) reads as bundled options instead.
+    let first: String = word.chars().take(2).collect();
+    if !word.chars().skip(2).all(|c| c.is_ascii_alphabetic())
+        && let Some(found) = find_flag(parsed, &first)
+        && takes_value(parsed, &first).is_some()
+    {
+        return (vec![flag_token(word, &found, examples)], None);
+    }
+
+    // `-xzvf`: every letter is its own option; only the last one may take the next word.
+    let letters: Vec<String> = word[1..].chars().map(|c| format!("-{c}")).collect();
+    let mut tokens = Vec::new();
+    let mut value = None;
+    for (i, letter) in letters.iter().enumerate() {
+        match find_flag(parsed, letter) {
+            Some(found) => {
+                if i + 1 == letters.len() {
+                    value = takes_value(parsed, letter).map(|v| (found.example, v));
+                }
+                tokens.push(flag_token(letter, &found, examples));
+            }
+            None => tokens.push(TokenExplanation::unexplained(letter, TokenRole::Flag)),
+        }
+    }
+    (tokens, value)
+}
+
+fn flag_token(word: &str, found: &FoundFlag, examples: &[Example]) -> TokenExplanation {
+    TokenExplanation {
+        aliases: found.aliases.clone(),
+        ..TokenExplanation::from_example(word, TokenRole::Flag, &examples[found.example])
+    }
+}
+
+struct FoundFlag {
+    example: usize,
+    aliases: Vec<String>,
+}
+
+/// The most focused example using the flag: among those that have it, the one with the fewest flags.
+fn find_flag(parsed: &[ExampleSyntax], name: &str) -> Option<FoundFlag> {
+    parsed
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| {
+            p.flags
+                .iter()
+                .find(|f| f.spellings.iter().any(|s| s == name))
+                .map(|f| (i, p.flags.len(), f))
+        })
+        .min_by_key(|(i, flag_count, _)| (*flag_count, *i))
+        .map(|(example, _, flag)| FoundFlag {
+            example,
+            aliases: if flag.spellings.len() > 1 {
+                flag.spellings.clone()
+            } else {
+                vec![]
+            },
+        })
+}
+
+/// The placeholder a flag consumes, when every example using it follows it with one.
+/// `ls {{[-a|--all]}} {{path}}` alone would read `path` as the value of `-a`; another
+/// example using `-a` last settles it.
+fn takes_value(parsed: &[ExampleSyntax], name: &str) -> Option<String> {
+    let mut values = parsed
+        .iter()
+        .flat_map(|p| &p.flags)
+        .filter(|f| f.spellings.iter().any(|s| s == name))
+        .map(|f| f.value.clone());
+    let first = values.next()??;
+    values.all(|v| v.is_some()).then_some(first)
+}
+
+/// Flags, literal words and positional placeholders of one example, after the command name.
+#[derive(Debug, Default)]
+struct ExampleSyntax {
+    flags: Vec<ExampleFlag>,
+    /// Literal words such as subcommands (`install` in `apt install {{package}}`).
+    literals: Vec<String>,
+    /// Positional placeholders, in order.
+    slots: Vec<String>,
+}
+
+impl ExampleSyntax {
+    fn mentions(&self, word: &str) -> bool {
+        self.literals.iter().any(|l| l == word)
+            || self
+                .flags
+                .iter()
+                .any(|f| f.spellings.iter().any(|s| s == word))
+    }
+}
+
+#[derive(Debug)]
+struct ExampleFlag {
+    /// `-f` or, for `{{[-f|--force]}}`, every alternate.
+    spellings: Vec<String>,
+    /// Placeholder written right after the flag (`{{path/to/dir}}` in `-C {{path/to/dir}}`).
+    value: Option<String>,
+}
+
+fn parse_example(syntax: &str, command_words: usize) -> ExampleSyntax {
+    let tokens: Vec<CommandToken> = tokenize_command(syntax)
+        .into_iter()
+        .filter(|t| !matches!(t, CommandToken::Whitespace(_)))
+        .skip(command_words)
+        .collect();
+
+    let mut parsed = ExampleSyntax::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let spellings = match &tokens[i] {
+            CommandToken::Word(w) if OPERATORS.contains(&w.as_str()) => break,
+            CommandToken::Word(w) if is_flag(w) => vec![flag_name(w).to_string()],
+            CommandToken::Word(w) => {
+                parsed.literals.push(w.clone());
+                i += 1;
+                continue;
+            }
+            CommandToken::Placeholder { inner, .. } => match flag_alternates(inner) {
+                Some(alternates) => alternates,
+                None => {
+                    parsed.slots.push(inner.clone());
+                    i += 1;
+                    continue;
+                }
+            },
+            CommandToken::Whitespace(_) => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let value = match tokens.get(i + 1) {
+            Some(CommandToken::Placeholder { inner, .. }) if flag_alternates(inner).is_none() => {
+                i += 1;
+                Some(inner.clone())
+            }
+            _ => None,
+        };
+
+        // Bundled short options (`tar -czf`) document each letter too; the last takes the value.
+        let bundled = &spellings[0];
+        if spellings.len() == 1
+            && bundled.len() > 2
+            && !bundled.starts_with("--")
+            && bundled[1..].chars().all(|c| c.is_ascii_alphabetic())
+        {
+            let letters: Vec<char> = bundled[1..].chars().collect();
+            for (n, c) in letters.iter().enumerate() {
+                parsed.flags.push(ExampleFlag {
+                    spellings: vec![format!("-{c}")],
+                    value: (n + 1 == letters.len()).then(|| value.clone()).flatten(),
+                });
+            }
+        }
+        parsed.flags.push(ExampleFlag { spellings, value });
+        i += 1;
+    }
+    parsed
+}
+
+/// `[-f|--force]` -> `["-f", "--force"]`; `None` for ordinary placeholders.
+fn flag_alternates(inner: &str) -> Option<Vec<String>> {
+    let body = inner.strip_prefix('[')?.strip_suffix(']')?;
+    let alternates: Vec<String> = body.split('|').map(|a| a.trim().to_string()).collect();
+    alternates.iter().all(|a| is_flag(a)).then_some(alternates)
+}
+
+fn is_flag(word: &str) -> bool {
+    word.len() > 1 && word.starts_with('-')
+}
+
+/// `--file=x` -> `--file`.
+fn flag_name(word: &str) -> &str {
+    word.split('=').next().unwrap_or(word)
+}
+
+/// Splits a command line at `|`, `||`, `&&` and `;` outside quotes.
+pub fn split_segments(line: &str) -> Vec<(Option<&'static str>, String)> {
+    let mut segments = Vec::new();
+    let mut operator = None;
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        if quote.is_none()
+            && let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op))
+        {
+            push_segment(&mut segments, operator, &mut current);
+            operator = Some(*op);
+            rest = &rest[op.len()..];
+            continue;
+        }
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (q, '\\') if q != Some('\'') => {
+                current.push(c);
+                rest = &rest[1..];
+                if let Some(escaped) = rest.chars().next() {
+                    current.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    push_segment(&mut segments, operator, &mut current);
+    segments
+}
+
+fn push_segment(
+    segments: &mut Vec<(Option<&'static str>, String)>,
+    operator: Option<&'static str>,
+    current: &mut String,
+) {
+    let text = current.trim().to_string();
+    current.clear();
+    if !text.is_empty() {
+        segments.push((operator, text));
+    }
+}
+
+/// Splits a segment into words the way a POSIX shell would, removing quotes.
+pub fn shell_words(segment: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = segment.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (Some(q), c) if q == c => quote = None,
+            (q, '\\') if q != Some('\'') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_word = true;
+            }
+            _ => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn page(command: &str, description: &str, examples: &[(&str, &str)]) -> Page {
+        Page {
+            command: command.to_string(),
+            platform: "common".to_string(),
+            description: description.to_string(),
+            examples: examples
+                .iter()
+                .map(|(desc, syntax)| Example::new(*desc, *syntax))
+                .collect(),
+            variants: vec![],
+        }
+    }
+
+    fn pages() -> HashMap<String, Page> {
+        [
+            page(
+                "tar",
+                "Archiving utility. More information: https://www.gnu.org/software/tar.",
+                &[
+                    (
+                        "E[x]tract a (compressed) archive [f]ile into the current directory [v]erbosely",
+                        "tar {{[-x|--extract]}} {{[-v|--verbose]}} {{[-f|--file]}} {{path/to/source.tar[.gz|.bz2|.xz]}}",
+                    ),
+                    (
+                        "E[x]tract a (compressed) archive [f]ile into the target directory",
+                        "tar {{[-x|--extract]}} {{[-f|--file]}} {{path/to/source.tar[.gz|.bz2|.xz]}} {{[-C|--directory]}} {{path/to/directory}}",
+                    ),
+                    (
+                        "[c]reate a g[z]ipped archive and write it to a [f]ile",
+                        "tar -czf {{path/to/target.tar.gz}} {{path/to/file1 path/to/file2 ...}}",
+                    ),
+                ],
+            ),
+            page(
+                "grep",
+                "Find patterns in files.",
+                &[
+                    ("Search for a pattern within files", "grep \"{{search_pattern}}\" {{path/to/file1 path/to/file2 ...}}"),
+                    ("Search case-insensitively", "grep {{[-i|--ignore-case]}} \"{{search_pattern}}\" {{path/to/file}}"),
+                ],
+            ),
+            page(
+                "git-stash",
+                "Stash local Git changes.",
+                &[("Apply the latest stash and delete it", "git stash pop")],
+            ),
+        ]
+        .into_iter()
+        .map(|p| (p.command.clone(), p))
+        .collect()
+    }
+
+    fn explain(line: &str) -> Vec<Segment> {
+        let pages = pages();
+        explain_line(line, |name| Ok(pages.get(name).cloned())).unwrap()
+    }
+
+    fn roles(segment: &Segment) -> Vec<(&str, TokenRole, bool)> {
+        segment
+            .tokens
+            .iter()
+            .map(|t| (t.token.as_str(), t.role, t.explained))
+            .collect()
+    }
+
+    #[test]
+    fn splits_pipelines_and_chains_outside_quotes() {
+        let segments = split_segments("tar xf a.tar && grep 'a|b' x | wc -l; ls");
+        assert_eq!(
+            segments,
+            vec![
+                (None, "tar xf a.tar".to_string()),
+                (Some("&&"), "grep 'a|b' x".to_string()),
+                (Some("|"), "wc -l".to_string()),
+                (Some(";"), "ls".to_string()),
+            ]
+        );
+        assert_eq!(
+            shell_words(r#"grep "two words" it\'s"#),
+            vec!["grep", "two words", "it's"]
+        );
+    }
+
+    #[test]
+    fn explains_bundled_flags_values_and_arguments() {
+        let segments = explain("tar -xzvf foo.tgz -C /tmp | grep -i conf");
+        assert_eq!(segments.len(), 2);
+
+        let tar = &segments[0];
+        assert_eq!(tar.command.as_deref(), Some("tar"));
+        assert_eq!(tar.description.as_deref(), Some("Archiving utility."));
+        assert_eq!(
+            roles(tar),
+            vec![
+                ("tar", TokenRole::Command, true),
+                ("-x", TokenRole::Flag, true),
+                ("-z", TokenRole::Flag, true),
+                ("-v", TokenRole::Flag, true),
+                ("-f", TokenRole::Flag, true),
+                ("foo.tgz", TokenRole::Value, true),
+                ("-C", TokenRole::Flag, true),
+                ("/tmp", TokenRole::Value, true),
+            ]
+        );
+        let x = &tar.tokens[1];
+        assert_eq!(x.aliases, vec!["-x", "--extract"]);
+        assert_eq!(
+            x.explanation.as_deref(),
+            Some("Extract a (compressed) archive file into the current directory verbosely")
+        );
+        // `-z` is only documented inside the bundled `-czf` of the create example.
+        assert!(
+            tar.tokens[2]
+                .example
+                .as_deref()
+                .unwrap()
+                .starts_with("tar -czf")
+        );
+        assert_eq!(
+            tar.tokens[7].placeholder.as_deref(),
+            Some("path/to/directory")
+        );
+
+        let grep = &segments[1];
+        assert_eq!(grep.operator.as_deref(), Some("|"));
+        assert_eq!(
+            roles(grep),
+            vec![
+                ("grep", TokenRole::Command, true),
+                ("-i", TokenRole::Flag, true),
+                ("conf", TokenRole::Argument, true),
+            ]
+        );
+        assert_eq!(
+            grep.tokens[2].placeholder.as_deref(),
+            Some("search_pattern")
+        );
+    }
+
+    #[test]
+    fn flags_what_it_cannot_explain() {
+        let segments = explain("grep -Q pattern a b c && frobnicate --all; git stash pop");
+        assert_eq!(segments[0].unexplained().collect::<Vec<_>>(), vec!["-Q"]);
+        // The trailing `...` placeholder absorbs every remaining file.
+        assert!(segments[0].tokens[3..].iter().all(|t| t.explained));
+
+        assert_eq!(segments[1].command, None);
+        assert_eq!(
+            segments[1].unexplained().collect::<Vec<_>>(),
+            vec!["frobnicate", "--all"]
+        );
+
+        let git = &segments[2];
+        assert_eq!(git.command.as_deref(), Some("git-stash"));
+        assert_eq!(
+            roles(git),
+            vec![
+                ("git", TokenRole::Command, true),
+                ("stash", TokenRole::Subcommand, true),
+                ("pop", TokenRole::Argument, true),
+            ]
+        );
+    }
+}
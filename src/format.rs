@@ -14,50 +14,63 @@ pub fn colorize_shell_word(word: &str, is_first: bool) -> String {
     }
 }
 
-pub fn highlight_command(ex_cmd: &str) -> String {
-    let mut highlighted_cmd = String::new();
-    let mut in_variable = false;
-    let mut is_first_word = true;
+/// One piece of a tldr example command, as split by `tokenize_command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandToken {
+    /// Literal shell word outside any `{{...}}` block.
+    Word(String),
+    /// Contents of a `{{...}}` block; `offset` is the byte index of the opening `{{`.
+    Placeholder {
+        inner: String,
+        offset: usize,
+    },
+    Whitespace(char),
+}
 
-    // Simple tokenizer that respects the {{var}} syntax from tldr before stripping it
+/// Simple tokenizer that respects the {{var}} syntax from tldr before stripping it
+pub fn tokenize_command(ex_cmd: &str) -> Vec<CommandToken> {
+    let mut tokens = Vec::new();
+    let mut in_variable = false;
+    let mut var_offset = 0;
     let mut current_word = String::new();
     let mut i = 0;
-    let chars: Vec<char> = ex_cmd.chars().collect();
+    let chars: Vec<(usize, char)> = ex_cmd.char_indices().collect();
 
     while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+
         // Check for variable start {{
-        if i + 1 < chars.len() && chars[i] == '{' && chars[i + 1] == '{' {
+        if c == '{' && next == Some('{') {
             if !current_word.is_empty() {
-                highlighted_cmd.push_str(&colorize_shell_word(&current_word, is_first_word));
-                is_first_word = false;
-                current_word.clear();
+                tokens.push(CommandToken::Word(std::mem::take(&mut current_word)));
             }
             in_variable = true;
+            var_offset = chars[i].0;
             i += 2;
             continue;
         }
 
         // Check for variable end }}
-        if i + 1 < chars.len() && chars[i] == '}' && chars[i + 1] == '}' {
+        if c == '}' && next == Some('}') {
             if !current_word.is_empty() {
-                // Variables are colored yellow
-                highlighted_cmd.push_str(&current_word.yellow().to_string());
-                current_word.clear();
+                tokens.push(CommandToken::Placeholder {
+                    inner: std::mem::take(&mut current_word),
+                    offset: var_offset,
+                });
             }
             in_variable = false;
             i += 2;
             continue;
         }
 
-        if chars[i].is_whitespace() && !in_variable {
+        if c.is_whitespace() && !in_variable {
             if !current_word.is_empty() {
-                highlighted_cmd.push_str(&colorize_shell_word(&current_word, is_first_word));
-                is_first_word = false;
-                current_word.clear();
+                tokens.push(CommandToken::Word(std::mem::take(&mut current_word)));
             }
-            highlighted_cmd.push(chars[i]);
+            tokens.push(CommandToken::Whitespace(c));
         } else {
-            current_word.push(chars[i]);
+            current_word.push(c);
         }
         i += 1;
     }
@@ -65,9 +78,45 @@ pub fn highlight_command(ex_cmd: &str) -> String {
     // Push any remaining text
     if !current_word.is_empty() {
         if in_variable {
-            highlighted_cmd.push_str(&current_word.yellow().to_string());
+            tokens.push(CommandToken::Placeholder {
+                inner: current_word,
+                offset: var_offset,
+            });
         } else {
-            highlighted_cmd.push_str(&colorize_shell_word(&current_word, is_first_word));
+            tokens.push(CommandToken::Word(current_word));
+        }
+    }
+
+    tokens
+}
+
+/// Drops the trailing "More information:" / "See also:" links from a tldr page description.
+pub fn clean_description(description: &str) -> &str {
+    let mut clean_desc = description;
+    if let Some(idx) = clean_desc.find(" More information:") {
+        clean_desc = &clean_desc[..idx];
+    }
+    if let Some(idx) = clean_desc.find(" See also:") {
+        clean_desc = &clean_desc[..idx];
+    }
+    clean_desc
+}
+
+pub fn highlight_command(ex_cmd: &str) -> String {
+    let mut highlighted_cmd = String::new();
+    let mut is_first_word = true;
+
+    for token in tokenize_command(ex_cmd) {
+        match token {
+            CommandToken::Word(word) => {
+                highlighted_cmd.push_str(&colorize_shell_word(&word, is_first_word));
+                is_first_word = false;
+            }
+            // Variables are colored yellow
+            CommandToken::Placeholder { inner, .. } => {
+                highlighted_cmd.push_str(&inner.yellow().to_string());
+            }
+            CommandToken::Whitespace(c) => highlighted_cmd.push(c),
         }
     }
 
@@ -104,4 +153,41 @@ mod tests {
         // Check that variable is highlighted (yellow is usually 33)
         assert!(highlighted.contains("33mfile\x1b[0m"));
     }
+
+    #[test]
+    fn test_tokenize_command_placeholders() {
+        let tokens = tokenize_command("tar xf {{path/to/source.tar}} -C {{path/to/dir}}");
+        assert_eq!(
+            tokens,
+            vec![
+                CommandToken::Word("tar".to_string()),
+                CommandToken::Whitespace(' '),
+                CommandToken::Word("xf".to_string()),
+                CommandToken::Whitespace(' '),
+                CommandToken::Placeholder {
+                    inner: "path/to/source.tar".to_string(),
+                    offset: 7,
+                },
+                CommandToken::Whitespace(' '),
+                CommandToken::Word("-C".to_string()),
+                CommandToken::Whitespace(' '),
+                CommandToken::Placeholder {
+                    inner: "path/to/dir".to_string(),
+                    offset: 33,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_keeps_spaces_inside_placeholders() {
+        let tokens = tokenize_command("rm {{file1 file2 ...}}");
+        assert_eq!(
+            tokens.last(),
+            Some(&CommandToken::Placeholder {
+                inner: "file1 file2 ...".to_string(),
+                offset: 3,
+            })
+        );
+    }
 }
@@ -0,0 +1,261 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Query tokens shorter than this are too ambiguous to correct (`cp` vs `cd`).
+const MIN_TOKEN_LEN: usize = 4;
+/// Substituting a neighbouring key is the most common slip, so it costs less.
+const ADJACENT_KEY_COST: f64 = 0.5;
+/// Suffixes that turn a command name into an ordinary word (`file` -> `files`, `serve` -> `server`).
+const INFLECTIONS: &[&str] = &["s", "es", "d", "ed", "r", "er", "ing"];
+
+const KEYBOARD_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+/// Horizontal stagger of each row, in key widths.
+const ROW_OFFSETS: [f64; 3] = [0.0, 0.25, 0.75];
+
+/// A query token assumed to be a misspelled command name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TermCorrection {
+    /// The token as the user typed it; matched against the query case-insensitively.
+    pub original: String,
+    pub corrected: String,
+    /// Weighted edit cost of the correction (adjacent-key slips cost 0.5).
+    pub cost: f64,
+}
+
+impl TermCorrection {
+    /// 1.0 for a near-certain slip, approaching 0 as the edit grows relative to the name.
+    pub fn similarity(&self) -> f64 {
+        (1.0 - self.cost / self.corrected.chars().count() as f64).clamp(0.0, 1.0)
+    }
+}
+
+/// Largest edit cost accepted for a token of this length.
+fn max_cost(len: usize) -> f64 {
+    if len >= 8 { 2.0 } else { 1.0 }
+}
+
+/// Finds query tokens that are close misspellings of a known command name.
+/// `is_known_word` should return true for words that occur in the corpus; those are real
+/// words ("change" is not a typo of `chage`) and are never corrected. A token whose two best
+/// candidates tie is ambiguous and left alone.
+pub fn correct_terms(
+    query: &str,
+    commands: &[String],
+    mut is_known_word: impl FnMut(&str) -> bool,
+) -> Vec<TermCorrection> {
+    let mut corrections: Vec<TermCorrection> = Vec::new();
+
+    for original in query_tokens(query) {
+        let token = original.to_lowercase();
+        let len = token.chars().count();
+        if len < MIN_TOKEN_LEN
+            || commands.iter().any(|c| c == &token)
+            || corrections
+                .iter()
+                .any(|c| c.original.to_lowercase() == token)
+        {
+            continue;
+        }
+
+        let limit = max_cost(len);
+        let mut best: Option<(f64, &String)> = None;
+        let mut tied = false;
+        for command in commands {
+            if command.chars().next() != token.chars().next()
+                || command.chars().count().abs_diff(len) as f64 > limit
+                || is_inflection(&token, command)
+            {
+                continue;
+            }
+            let cost = edit_cost(&token, command);
+            if cost > limit {
+                continue;
+            }
+            match best {
+                Some((best_cost, _)) if cost > best_cost => {}
+                Some((best_cost, _)) if cost == best_cost => tied = true,
+                _ => {
+                    best = Some((cost, command));
+                    tied = false;
+                }
+            }
+        }
+
+        if let Some((cost, command)) = best
+            && !tied
+            && !is_known_word(&token)
+        {
+            corrections.push(TermCorrection {
+                original,
+                corrected: command.clone(),
+                cost,
+            });
+        }
+    }
+
+    corrections
+}
+
+/// Rewrites the query with every correction applied, keeping surrounding punctuation.
+pub fn apply_corrections(query: &str, corrections: &[TermCorrection]) -> String {
+    if corrections.is_empty() {
+        return query.to_string();
+    }
+    query
+        .split_whitespace()
+        .map(|word| {
+            let core = word.trim_matches(|c: char| !is_token_char(c));
+            let lower = core.to_lowercase();
+            match corrections
+                .iter()
+                .find(|c| c.original.to_lowercase() == lower)
+            {
+                Some(c) if !core.is_empty() => word.replacen(core, &c.corrected, 1),
+                _ => word.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '.'
+}
+
+fn query_tokens(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !is_token_char(c)).to_string())
+        .filter(|w| !w.is_empty() && !w.starts_with('-'))
+        .collect()
+}
+
+fn is_inflection(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long.strip_prefix(short)
+        .is_some_and(|suffix| INFLECTIONS.contains(&suffix))
+}
+
+/// Optimal string alignment distance where neighbouring-key substitutions cost less.
+pub fn edit_cost(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0.0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i as f64;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j as f64;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = if a[i - 1] == b[j - 1] {
+                0.0
+            } else if keys_adjacent(a[i - 1], b[j - 1]) {
+                ADJACENT_KEY_COST
+            } else {
+                1.0
+            };
+            let mut cost = (d[i - 1][j] + 1.0)
+                .min(d[i][j - 1] + 1.0)
+                .min(d[i - 1][j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cost = cost.min(d[i - 2][j - 2] + 1.0);
+            }
+            d[i][j] = cost;
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+fn key_position(c: char) -> Option<(usize, f64)> {
+    let c = c.to_ascii_lowercase();
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, keys)| keys.find(c).map(|col| (row, col as f64 + ROW_OFFSETS[row])))
+}
+
+/// True for keys that touch on a QWERTY layout.
+pub fn keys_adjacent(a: char, b: char) -> bool {
+    let (Some((row_a, x_a)), Some((row_b, x_b))) = (key_position(a), key_position(b)) else {
+        return false;
+    };
+    let dx = (x_a - x_b).abs();
+    match row_a.abs_diff(row_b) {
+        0 => dx == 1.0,
+        1 => dx <= 0.75,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn corrects_transpositions_and_omissions() {
+        let known = commands(&["rsync", "kubectl", "kubectx", "ls", "scp"]);
+        let corrections = correct_terms("rsycn files to server", &known, |_| false);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].original, "rsycn");
+        assert_eq!(corrections[0].corrected, "rsync");
+        assert_eq!(corrections[0].cost, 1.0);
+
+        let corrections = correct_terms("Kubctl logs", &known, |_| false);
+        assert_eq!(corrections[0].original, "Kubctl");
+        assert_eq!(corrections[0].corrected, "kubectl");
+        assert_eq!(
+            apply_corrections("Kubctl logs", &corrections),
+            "kubectl logs"
+        );
+        assert_eq!(
+            correct_terms("RSYNC kubctl KUBCTL", &known, |_| false).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn adjacent_keys_cost_less() {
+        assert!(keys_adjacent('r', 'f'));
+        assert!(keys_adjacent('g', 'h'));
+        assert!(!keys_adjacent('q', 'p'));
+        assert_eq!(edit_cost("grwp", "grep"), ADJACENT_KEY_COST);
+        assert_eq!(edit_cost("grxp", "grep"), 1.0);
+    }
+
+    #[test]
+    fn leaves_real_words_and_exact_names_alone() {
+        let known = commands(&["chage", "file", "serve", "users", "grep"]);
+        // Known corpus words are never treated as typos.
+        assert!(correct_terms("change password expiry", &known, |w| w == "change").is_empty());
+        // Inflected forms of a command name are ordinary words.
+        assert!(correct_terms("list files on the server", &known, |_| false).is_empty());
+        assert!(correct_terms("grep user accounts", &known, |_| false).is_empty());
+    }
+
+    #[test]
+    fn ambiguous_tokens_are_not_corrected() {
+        let known = commands(&["pingx", "pingy"]);
+        assert!(correct_terms("pingq host", &known, |_| false).is_empty());
+    }
+
+    #[test]
+    fn rewrites_query_keeping_punctuation() {
+        let corrections = vec![TermCorrection {
+            original: "rsycn".to_string(),
+            corrected: "rsync".to_string(),
+            cost: 1.0,
+        }];
+        assert_eq!(
+            apply_corrections("Rsycn, then copy", &corrections),
+            "rsync, then copy"
+        );
+        assert!((corrections[0].similarity() - 0.8).abs() < 1e-9);
+    }
+}
@@ -8,7 +8,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use zerocopy::IntoBytes;
 
-use askman::db;
+use askman::{db, search};
 
 /// Downloads and extracts the tldr-pages repo zip into a temp directory.
 /// Returns the path to the extracted `pages/` folder (e.g. /tmp/askman_tldr/tldr-main/pages).
@@ -65,6 +65,7 @@ fn main() -> Result<()> {
     let conn = Connection::open(&db_path).context("Failed to open database")?;
 
     conn.execute("DROP TABLE IF EXISTS pages_vec", [])?;
+    conn.execute("DROP TABLE IF EXISTS pages_fts", [])?;
 
     // os column tags each command by platform for filtered queries
     conn.execute(
@@ -79,8 +80,12 @@ fn main() -> Result<()> {
         [],
     )?;
 
+    create_fts_table(&conn)?;
+
     let mut count = 0;
-    for os_type in ["common", "linux", "osx", "windows"] {
+    for os_type in [
+        "common", "linux", "osx", "windows", "freebsd", "openbsd", "netbsd",
+    ] {
         let dir = pages_dir.join(os_type);
         if dir.exists() {
             println!("Processing directory: {}", os_type);
@@ -90,6 +95,10 @@ fn main() -> Result<()> {
         }
     }
 
+    let families = search::derive_command_families(&conn)?;
+    search::store_command_families(&conn, &families)?;
+    println!("Recorded {} subcommand pages", families.len());
+
     // Clean up temp directory
     let tmp_dir = std::env::temp_dir().join("askman_tldr");
     fs::remove_dir_all(&tmp_dir).ok();
@@ -99,6 +108,22 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// FTS5 index over the text columns of `pages_vec`, keyed by the same rowid.
+/// `os` is stored unindexed so search can apply the same platform filter as the KNN query.
+fn create_fts_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE VIRTUAL TABLE pages_fts USING fts5(
+            command,
+            description,
+            example_desc,
+            example_cmd,
+            os UNINDEXED
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
 /// Reads all .md files in a tldr directory, embeds each example, and inserts into SQLite.
 fn process_directory(
     dir_path: &Path,
@@ -161,6 +186,20 @@ fn process_directory(
                 ],
             )?;
 
+            // Lexical twin of the vector row; sharing the rowid lets search fuse both rankings.
+            conn.execute(
+                "INSERT INTO pages_fts(rowid, command, description, example_desc, example_cmd, os)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    conn.last_insert_rowid(),
+                    command,
+                    description,
+                    example_desc,
+                    example_cmd,
+                    os_tag
+                ],
+            )?;
+
             count += 1;
         }
     }
@@ -320,6 +359,37 @@ mod tests {
         assert!(examples.is_empty());
     }
 
+    // --- lexical index ---
+
+    #[test]
+    fn test_fts_table_matches_flag_names() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        create_fts_table(&conn)?;
+
+        conn.execute(
+            "INSERT INTO pages_fts(rowid, command, description, example_desc, example_cmd, os)
+             VALUES (7, 'rsync', 'Transfer files.', 'Resume partial transfers', 'rsync --partial {{src}} {{dst}}', 'common')",
+            [],
+        )?;
+
+        let rowid: i64 = conn.query_row(
+            "SELECT rowid FROM pages_fts WHERE pages_fts MATCH '\"partial\"'",
+            [],
+            |r| r.get(0),
+        )?;
+        assert_eq!(rowid, 7);
+
+        // os is filterable but not searchable
+        let os_hits: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pages_fts WHERE pages_fts MATCH '\"common\"'",
+            [],
+            |r| r.get(0),
+        )?;
+        assert_eq!(os_hits, 0);
+
+        Ok(())
+    }
+
     // --- database creation (existing test) ---
 
     #[test]
@@ -0,0 +1,130 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Shell builtins never show up on `$PATH` but are always available.
+const SHELL_BUILTINS: &[&str] = &[
+    "alias", "bg", "bind", "builtin", "cd", "command", "declare", "dirs", "disown", "eval", "exec",
+    "exit", "export", "fg", "hash", "help", "history", "jobs", "let", "local", "popd", "pushd",
+    "read", "set", "shopt", "source", "trap", "type", "ulimit", "umask", "unset", "wait",
+];
+
+/// Resolves a tldr page name against `$PATH`.
+/// Subcommand pages (e.g. `docker-cp`) count as installed when their `family` tool is. Pass the
+/// family from `command_families` only: a dashed name alone (`git-flow`) may be its own tool.
+pub fn is_installed(command: &str, family: Option<&str>) -> bool {
+    let Some(path_var) = std::env::var_os("PATH") else {
+        return SHELL_BUILTINS.contains(&command);
+    };
+    is_installed_in(command, family, &path_var)
+}
+
+pub fn is_installed_in(command: &str, family: Option<&str>, path_var: &OsStr) -> bool {
+    if SHELL_BUILTINS.contains(&command) {
+        return true;
+    }
+    if find_in_path(command, path_var).is_some() {
+        return true;
+    }
+    family.is_some_and(|family| find_in_path(family, path_var).is_some())
+}
+
+/// Returns the first executable named `name` in the given `PATH`-style list.
+pub fn find_in_path(name: &str, path_var: &OsStr) -> Option<PathBuf> {
+    for dir in std::env::split_paths(path_var) {
+        for candidate in executable_names(name) {
+            let path = dir.join(&candidate);
+            if is_executable(&path) {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(windows)]
+fn executable_names(name: &str) -> Vec<String> {
+    let exts = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    let mut names = vec![name.to_string()];
+    names.extend(
+        exts.split(';')
+            .filter(|e| !e.is_empty())
+            .map(|e| format!("{name}{e}")),
+    );
+    names
+}
+
+#[cfg(not(windows))]
+fn executable_names(name: &str) -> Vec<String> {
+    vec![name.to_string()]
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn fake_bin_dir(name: &str, bins: &[(&str, u32)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("askman_path_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (bin, mode) in bins {
+            let path = dir.join(bin);
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(*mode)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn finds_executables_only() {
+        let dir = fake_bin_dir("exec", &[("fd", 0o755), ("notes", 0o644)]);
+        let path_var = dir.as_os_str();
+
+        assert_eq!(find_in_path("fd", path_var), Some(dir.join("fd")));
+        assert!(find_in_path("notes", path_var).is_none());
+        assert!(find_in_path("rg", path_var).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn subcommand_pages_resolve_to_parent_tool() {
+        let dir = fake_bin_dir("family", &[("docker", 0o755)]);
+        let path_var = dir.as_os_str();
+
+        assert!(is_installed_in("docker-cp", Some("docker"), path_var));
+        assert!(!is_installed_in("podman-cp", Some("podman"), path_var));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dashed_names_outside_a_family_need_their_own_binary() {
+        let dir = fake_bin_dir("dashed", &[("git", 0o755)]);
+        let path_var = dir.as_os_str();
+
+        assert!(!is_installed_in("git-flow", None, path_var));
+        assert!(!is_installed_in("-x", None, path_var));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shell_builtins_are_always_installed() {
+        assert!(is_installed_in("cd", None, OsStr::new("")));
+        assert!(!is_installed_in("ls", None, OsStr::new("")));
+    }
+}
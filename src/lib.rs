@@ -1,6 +1,21 @@
+pub mod batch;
+pub mod calibration;
+pub mod check;
 pub mod cli;
+pub mod daemon;
 pub mod db;
 pub mod embed;
+pub mod eval;
+pub mod explain;
 pub mod format;
+pub mod fuzzy;
+pub mod installed;
+pub mod mcp;
+pub mod output;
+pub mod pipeline;
+pub mod placeholder;
+pub mod profile;
+pub mod rerank;
+pub mod risk;
 pub mod search;
 pub mod update;
@@ -1,10 +1,19 @@
 use anyhow::Result;
 
-use askman::{cli, db, embed, format, search, update};
+use askman::pipeline::{
+    DEFAULT_TEXT_RESULTS, Pipeline, RankOptions, Ranking, ResultWindow, SearchRequest,
+    page_installed, page_output, page_suggestions, search_output, tool_suggestions,
+    unknown_tool_output,
+};
+use askman::{
+    batch, calibration, check, cli, daemon, db, eval, explain, format, mcp, output, placeholder,
+    profile, rerank, risk, search, update,
+};
 use clap::Parser;
 use colored::*;
 use rusqlite::ffi::sqlite3_auto_extension;
 use sqlite_vec::sqlite3_vec_init;
+use std::collections::HashMap;
 
 fn main() -> Result<()> {
     // Required: register sqlite-vec extension before opening any connection
@@ -18,6 +27,28 @@ fn main() -> Result<()> {
     if let Some(command) = &args.command {
         match command {
             cli::Command::Update => return update::run_update(),
+            cli::Command::DownloadReranker => {
+                let app_dir = db::get_app_dir()?;
+                println!("Downloading reranker model (this only happens once)...");
+                rerank::init_reranker(&app_dir, true)?;
+                println!(
+                    "Reranker ready at {:?}",
+                    rerank::reranker_cache_path(&app_dir)
+                );
+                return Ok(());
+            }
+            cli::Command::Eval(eval_args) => return run_eval(eval_args),
+            cli::Command::Calibrate(calibrate_args) => return run_calibrate(calibrate_args),
+            cli::Command::Show(show_args) => return run_show(show_args),
+            cli::Command::Explain(explain_args) => return run_explain(explain_args),
+            cli::Command::Check(check_args) => return run_check(check_args),
+            cli::Command::Mcp(mcp_args) => return mcp::run_mcp(mcp_args),
+            cli::Command::Schema => {
+                println!("{}", serde_json::to_string_pretty(&output::schema())?);
+                return Ok(());
+            }
+            cli::Command::Batch(batch_args) => return batch::run_batch(batch_args),
+            cli::Command::Daemon(daemon_args) => return daemon::run_daemon(daemon_args),
         }
     }
 
@@ -52,9 +83,13 @@ fn main() -> Result<()> {
     let conn = db::get_connection(&db_path)?;
 
     // CLI flags override auto-detection; default maps to host OS
-    let target_os = search::get_target_os(args.linux, args.osx, args.windows);
+    let (target_os, os_source) = search::get_target_os(args.linux, args.osx, args.windows);
+    let target_os = search::page_os(&conn, target_os)?;
+    let profile = profile::load_profile()?;
 
-    try_semantic_search(&conn, &query, &app_dir, target_os, args.verbose, args.json)
+    try_semantic_search(
+        &conn, &query, &app_dir, target_os, os_source, &profile, &args,
+    )
 }
 
 /// Embeds the query, runs KNN against sqlite-vec, ranks results, and prints output.
@@ -63,118 +98,520 @@ fn try_semantic_search(
     query: &str,
     app_dir: &std::path::Path,
     target_os: search::TargetOs,
-    verbose: bool,
-    output_json: bool,
+    os_source: search::OsSource,
+    profile: &profile::RankingProfile,
+    args: &cli::Args,
 ) -> Result<()> {
-    let embedder = embed::init_model(app_dir)?;
-    let q_vec = embed::embed_query(&embedder, query)?;
-    let mut sorted = search::perform_search(conn, query, &q_vec, target_os, output_json)?;
-
-    if output_json {
-        // JSON policy blocks thin complex results; hydrate the top hit with more examples
-        // so strong single-intent queries are less likely to be rejected as under-specified.
-        // Follow-up after exercising this: ensure hydration respects the requested OS or tags each example with its platform.
-        let _ = search::hydrate_top_result_examples(
-            conn,
-            &mut sorted,
+    let verbose = args.verbose;
+    let window = ResultWindow::from(args);
+    let bindings: HashMap<String, String> = args.fill.iter().cloned().collect();
+
+    // Resolve `--tool` before loading any model so a mistyped tool fails fast.
+    let scope = if args.tool.is_empty() {
+        None
+    } else {
+        let commands = search::load_command_names(conn)?;
+        let scope = search::resolve_tool_scope(
+            &args.tool,
+            &commands,
+            &search::load_command_families(conn)?,
+        );
+        if !scope.unknown.is_empty() {
+            report_unknown_tools(query, target_os, os_source, &scope, &commands, args.json)?;
+            std::process::exit(1);
+        }
+        Some(scope)
+    };
+
+    let options = RankOptions::from(args);
+    let rerank_enabled = args.rerank || profile.rerank.enabled;
+    let forwarded = daemon::DaemonQuery {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        profile: profile.clone(),
+        query: query.to_string(),
+        os: target_os.as_str().to_string(),
+        options,
+        tools: args.tool.clone(),
+        rerank: rerank_enabled,
+        path: std::env::var_os("PATH"),
+    };
+    let outcome = match daemon::ask_daemon(app_dir, &forwarded, verbose) {
+        Some(outcome) => outcome,
+        None => Pipeline::load(conn, app_dir, profile, rerank_enabled, options)?
+            .with_scope(scope.clone())
+            .search(query, target_os)?,
+    };
+    if let Some(reason) = &outcome.rerank.skipped_reason
+        && !args.json
+    {
+        eprintln!("{}", format!("Reranking skipped: {reason}").yellow());
+    }
+
+    if args.json {
+        let request = SearchRequest {
             query,
             target_os,
-            true,
-            search::HYDRATE_MIN_EXAMPLES,
-            search::HYDRATE_MAX_EXAMPLES,
-        )?;
-
-        let mut results_json = Vec::new();
-        for (i, (cmd, data)) in sorted.iter().enumerate().take(2) {
-            // Expose partial-intent mismatches directly to agents (`pass`/`warn` + missing terms).
-            let intent = search::evaluate_intent_coverage(query, cmd, data);
-
-            // Clean up description (strip "More information" and "See also" links)
-            let mut clean_desc = data.description.as_str();
-            if let Some(idx) = clean_desc.find(" More information:") {
-                clean_desc = &clean_desc[..idx];
-            }
-            if let Some(idx) = clean_desc.find(" See also:") {
-                clean_desc = &clean_desc[..idx];
-            }
+            os_source,
+            profile,
+            tools: scope.as_ref().map(|scope| scope.tools.as_slice()),
+            max_risk: args.max_risk,
+            window,
+            bindings: &bindings,
+            verbose,
+        };
+        let output = search_output(&request, &outcome);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-            // confidence for standard LLM agents:
-            // polynomial curve 1.0 - (dist / max)^7 to keep scores high
-            // this is a try of normalizing the  cosine distance to a confidence score
-            let ratio = (data.adjusted_score / search::MAX_DISTANCE).clamp(0.0, 1.0);
-            let confidence = 1.0 - ratio.powf(7.0);
-
-            // noise reduction:
-            // If we are not at least 50% confident, avoid it.
-            // If the absolute best result (#1) is a slam dunk (> 90%), and this result
-            // is a distant second (trailing by > 10%), also avoid it.
-            if i > 0 {
-                if confidence < 0.50 {
-                    break;
-                }
+    let (ranking, steps) = (&outcome.ranking, &outcome.steps);
+    if verbose {
+        let sources = if profile.sources.is_empty() {
+            "built-in defaults".to_string()
+        } else {
+            profile
+                .sources
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!(
+            "{}",
+            format!("(Profile: {} | Sources: {})", profile.name, sources).bright_black()
+        );
+    }
+
+    if !ranking.corrections.is_empty() {
+        let assumed = ranking
+            .corrections
+            .iter()
+            .map(|c| format!("{} -> {}", c.original, c.corrected))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("{}", format!("(Assuming {assumed})").bright_black());
+    }
+
+    if steps.is_empty() {
+        print_results(&ranking.results, &bindings, verbose, window);
+        return Ok(());
+    }
+
+    for (i, step) in steps.iter().enumerate() {
+        println!(
+            "{}",
+            format!("Step {}: {}", i + 1, step.query).bold().underline()
+        );
+        match step.results.first() {
+            Some((cmd, data)) => print_result(cmd, data, 1, &bindings, verbose),
+            None => println!("No good matches found.\n"),
+        }
+    }
+
+    Ok(())
+}
+
+/// `--tool` named something the database has no page for; nothing is searched.
+fn report_unknown_tools(
+    query: &str,
+    target_os: search::TargetOs,
+    os_source: search::OsSource,
+    scope: &search::ToolScope,
+    commands: &[String],
+    json: bool,
+) -> Result<()> {
+    let did_you_mean = tool_suggestions(scope, commands);
+    if json {
+        let output = unknown_tool_output(query, target_os, os_source, scope, did_you_mean);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        eprintln!("Unknown tool: {}.", scope.unknown.join(", "));
+        if !did_you_mean.is_empty() {
+            eprintln!("Did you mean: {}?", did_you_mean.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Explains an existing command line from the stored pages; like `show`, no model is loaded.
+fn run_explain(explain_args: &cli::ExplainArgs) -> Result<()> {
+    let line = explain_args.command_line.join(" ");
+    let (target_os, os_source) =
+        search::get_target_os(explain_args.linux, explain_args.osx, explain_args.windows);
 
-                // Need to compute the #1 result's confidence for the delta check
-                let top_score = sorted[0].1.adjusted_score;
-                let top_ratio = (top_score / search::MAX_DISTANCE).clamp(0.0, 1.0);
-                let top_confidence = 1.0 - top_ratio.powf(7.0);
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let target_os = search::page_os(&conn, target_os)?;
+    let segments = explain::explain_line(&line, |command| {
+        search::lookup_page(&conn, command, target_os)
+    })?;
+
+    if explain_args.json {
+        let output = output::ExplainOutput::new(&line, segments, target_os, os_source);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-                if top_confidence > 0.90 && (top_confidence - confidence) > 0.10 {
-                    break;
+    for segment in &segments {
+        let header = match &segment.operator {
+            Some(op) => format!("{op} {}", segment.text),
+            None => segment.text.clone(),
+        };
+        println!("{}", header.bold());
+        let width = segment
+            .tokens
+            .iter()
+            .map(|t| t.token.chars().count())
+            .max()
+            .unwrap_or(0);
+        for token in &segment.tokens {
+            let padded = format!("{:width$}", token.token);
+            match &token.explanation {
+                Some(explanation) => {
+                    let note = match &token.placeholder {
+                        Some(placeholder) => format!(" ({{{{{placeholder}}}}})").bright_black(),
+                        None => "".normal(),
+                    };
+                    println!("  {}  {explanation}{note}", padded.cyan());
                 }
+                None => println!(
+                    "  {}  {}",
+                    padded.yellow(),
+                    "not found in any example; check `man` or `--help`".yellow()
+                ),
             }
+        }
+        println!();
+    }
+    let unexplained: Vec<&str> = segments.iter().flat_map(|s| s.unexplained()).collect();
+    if !unexplained.is_empty() {
+        println!(
+            "{}",
+            format!("Unexplained: {}", unexplained.join(" ")).yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Verifies a proposed command line against the stored pages. Exits 0 on `pass`,
+/// 1 on `warn` and 2 on `unknown`, so shell callers can gate on it.
+fn run_check(check_args: &cli::CheckArgs) -> Result<()> {
+    let line = check_args.command_line.join(" ");
+    let (target_os, os_source) =
+        search::get_target_os(check_args.linux, check_args.osx, check_args.windows);
 
-            let mut result_obj = serde_json::json!({
-                "command": cmd,
-                "platform": data.platform,
-                "description": clean_desc.trim_end_matches([' ', '\n']).replace("[", "").replace("]", ""),
-                "confidence": (confidence * 10000.0).round() / 10000.0,
-                "intent": {
-                    "coverage": (intent.score * 10000.0).round() / 10000.0,
-                    "status": if intent.strong { "pass" } else { "warn" },
-                    "missing_terms": intent.missing_terms
-                },
-                "examples": data.examples.iter().map(|(desc, ex_cmd)| {
-                    serde_json::json!({
-                        "description": desc.replace("[", "").replace("]", ""),
-                        "syntax": ex_cmd
-                    })
-                }).collect::<Vec<_>>(),
-            });
-
-            if verbose {
-                if let Some(obj) = result_obj.as_object_mut() {
-                    obj.insert(
-                        "adjusted_distance".to_string(),
-                        serde_json::json!(data.adjusted_score),
-                    );
-                    obj.insert(
-                        "raw_distance".to_string(),
-                        serde_json::json!(data.raw_distance),
-                    );
-                    obj.insert(
-                        "heuristics_applied".to_string(),
-                        serde_json::json!(data.heuristics),
-                    );
-                    obj.insert(
-                        "intent_matched_terms".to_string(),
-                        serde_json::json!(intent.matched_terms),
-                    );
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let target_os = search::page_os(&conn, target_os)?;
+    let report = check::check_line(
+        &line,
+        |command| search::lookup_page(&conn, command, target_os),
+        |command| search::page_platforms(&conn, command),
+    )?;
+
+    if check_args.json {
+        let output = output::CheckOutput::new(&line, &report, target_os, os_source);
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        for command in &report.commands {
+            let name = command.command.as_deref().unwrap_or(&command.text);
+            let verdict = match command.status {
+                check::CheckStatus::Pass => command.status.as_str().green(),
+                check::CheckStatus::Warn => command.status.as_str().yellow(),
+                check::CheckStatus::Unknown => command.status.as_str().red(),
+            };
+            println!("{} {}", name.bold(), verdict);
+            if command.command.is_none() {
+                let note = if command.available_on.is_empty() {
+                    "no page in the database".to_string()
+                } else {
+                    format!(
+                        "no {} page; available on {}",
+                        target_os.as_str(),
+                        command.available_on.join(", ")
+                    )
+                };
+                println!("  {}", note.bright_black());
+            }
+            for flag in &command.flags {
+                match &flag.example {
+                    Some(example) => println!("  {}  {}", flag.flag.cyan(), example.bright_black()),
+                    None => println!(
+                        "  {}  {}",
+                        flag.flag.yellow(),
+                        "not used by any example".yellow()
+                    ),
                 }
             }
-            results_json.push(result_obj);
         }
+        if report.risk.level >= risk::RiskLevel::High {
+            println!(
+                "{}",
+                format!(
+                    "{} risk: {}",
+                    report.risk.level.as_str(),
+                    report.risk.reasons.join(", ")
+                )
+                .red()
+            );
+        }
+    }
+
+    match report.status {
+        check::CheckStatus::Pass => Ok(()),
+        check::CheckStatus::Warn => std::process::exit(1),
+        check::CheckStatus::Unknown => std::process::exit(2),
+    }
+}
 
-        let output = serde_json::json!({
-            "query": query,
-            "os": target_os.as_str(),
-            "results": results_json
-        });
+/// Prints a command's page by exact name. Never loads the embedding model.
+fn run_show(show_args: &cli::ShowArgs) -> Result<()> {
+    let command = show_args.command.join("-").to_lowercase();
+    let (target_os, os_source) =
+        search::get_target_os(show_args.linux, show_args.osx, show_args.windows);
 
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let target_os = search::page_os(&conn, target_os)?;
+
+    let Some(page) = search::lookup_page(&conn, &command, target_os)? else {
+        let did_you_mean = page_suggestions(&conn, &command)?;
+        if show_args.json {
+            let output =
+                output::MissingPageOutput::new(&command, target_os, os_source, did_you_mean);
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            eprintln!("No page for `{}` on {}.", command, target_os.as_str());
+            if !did_you_mean.is_empty() {
+                eprintln!("Did you mean: {}?", did_you_mean.join(", "));
+            }
+        }
+        std::process::exit(1);
+    };
+
+    let families = search::load_command_families(&conn)?;
+    if show_args.json {
+        let output = page_output(&page, &families, target_os, os_source);
         println!("{}", serde_json::to_string_pretty(&output)?);
         return Ok(());
     }
 
-    for (i, (cmd, data)) in sorted.iter().enumerate().take(3) {
+    if page_installed(&page, &families) {
+        println!("{}", page.command.bold().green());
+    } else {
+        println!(
+            "{} {}",
+            page.command.bold().green(),
+            "(not installed)".bright_black()
+        );
+    }
+    println!("{}", format::clean_description(&page.description));
+    if !page.examples.is_empty() {
+        println!("\n{}", "Examples:".underline());
+        for example in &page.examples {
+            println!("  {}", example.description);
+            println!("   {}", format::highlight_command(&example.syntax));
+            print_risk(&example.syntax);
+            println!();
+        }
+    }
+    if !page.variants.is_empty() {
+        println!("{}", "Subcommands:".underline());
+        println!("  {}", page.variants.join(", "));
+    }
+    Ok(())
+}
+
+/// Replays a JSONL file of expected answers through the agent (`--json`) pipeline and
+/// reports recall, MRR and intent pass-rate. Exits non-zero when a floor is missed.
+fn run_eval(eval_args: &cli::EvalArgs) -> Result<()> {
+    let cases = eval::load_cases(&eval_args.cases)?;
+
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let profile = profile::load_profile()?;
+    let ranked = rank_cases(&conn, &app_dir, &profile, &cases, eval_args.rerank)?;
+    let results = cases
+        .iter()
+        .zip(&ranked)
+        .map(|(case, (target_os, ranking))| {
+            eval::score_case(case, *target_os, &ranking.query, &ranking.results, &profile)
+        })
+        .collect();
+
+    let report = eval::EvalReport::new(
+        results,
+        &eval::Floors {
+            recall_at_1: eval_args.min_recall_at_1,
+            recall_at_3: eval_args.min_recall_at_3,
+            mrr: eval_args.min_mrr,
+            intent_pass_rate: eval_args.min_intent_pass_rate,
+        },
+    );
+
+    if eval_args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_eval_report(&report, &profile);
+    }
+
+    if !report.failures.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Fits confidence calibration on a labeled query set and stores it for the active profile.
+fn run_calibrate(calibrate_args: &cli::CalibrateArgs) -> Result<()> {
+    let cases = eval::load_cases(&calibrate_args.cases)?;
+
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let profile = profile::load_profile()?;
+
+    let ranked = rank_cases(&conn, &app_dir, &profile, &cases, false)?;
+    let samples: Vec<calibration::Sample> = cases
+        .iter()
+        .zip(&ranked)
+        .flat_map(|(case, (_, ranking))| {
+            calibration::label_results(
+                &ranking.query,
+                &case.expected_command,
+                &ranking.results,
+                &profile,
+            )
+        })
+        .collect();
+
+    let fitted = calibration::Calibration::fit(&samples)?;
+    println!("{}", serde_json::to_string_pretty(&fitted)?);
+    if !calibrate_args.dry_run {
+        let path = calibration::store_path()
+            .ok_or_else(|| anyhow::anyhow!("no config directory to store the calibration in"))?;
+        calibration::store(&path, &profile, &fitted)?;
+        println!(
+            "Stored calibration for profile `{}` in {}",
+            profile.name,
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Runs every labeled case through the agent (`--json`) pipeline on its own target OS.
+fn rank_cases(
+    conn: &rusqlite::Connection,
+    app_dir: &std::path::Path,
+    profile: &profile::RankingProfile,
+    cases: &[eval::EvalCase],
+    rerank: bool,
+) -> Result<Vec<(search::TargetOs, Ranking)>> {
+    // Reproducible across machines: never filter by what happens to be installed.
+    let options = RankOptions {
+        json: true,
+        installed_only: false,
+        depth: eval::TOP_SHOWN,
+        max_risk: None,
+    };
+    let pipeline = Pipeline::load(
+        conn,
+        app_dir,
+        profile,
+        rerank || profile.rerank.enabled,
+        options,
+    )?;
+    if let Some(reason) = &pipeline.rerank_skipped {
+        eprintln!("{}", format!("Reranking skipped: {reason}").yellow());
+    }
+
+    let (host_os, _) = search::get_target_os(false, false, false);
+    let mut ranked = Vec::with_capacity(cases.len());
+    for case in cases {
+        let target_os = search::page_os(conn, case.target_os(host_os)?)?;
+        ranked.push((target_os, pipeline.rank(&case.query, target_os)?));
+    }
+    Ok(ranked)
+}
+
+fn print_eval_report(report: &eval::EvalReport, profile: &profile::RankingProfile) {
+    for case in report.cases.iter().filter(|c| !c.passed()) {
+        let rank = case
+            .rank
+            .map_or("missing".to_string(), |k| format!("rank {k}"));
+        println!("{} {}", "✗".red(), case.query.bold());
+        println!(
+            "    expected {} ({}, {}), got [{}]",
+            case.expected_command.green(),
+            rank,
+            case.os,
+            case.top.join(", ")
+        );
+        if case.syntax_hit == Some(false)
+            && let Some(expected) = &case.expected_syntax
+        {
+            println!("    best example does not contain `{expected}`");
+        }
+        if !case.intent_missing_terms.is_empty() {
+            println!(
+                "    {}",
+                format!("missing terms: {}", case.intent_missing_terms.join(", ")).bright_black()
+            );
+        }
+    }
+
+    let m = &report.metrics;
+    let passed = report.cases.iter().filter(|c| c.passed()).count();
+    println!();
+    println!(
+        "{}",
+        format!(
+            "{passed}/{} cases passed (profile: {})",
+            m.cases, profile.name
+        )
+        .bold()
+    );
+    println!("  recall@1          {:.4}", m.recall_at_1);
+    println!("  recall@3          {:.4}", m.recall_at_3);
+    println!("  mrr               {:.4}", m.mrr);
+    println!("  intent pass-rate  {:.4}", m.intent_pass_rate);
+    if let Some(rate) = m.syntax_hit_rate {
+        println!("  syntax hit-rate   {:.4}", rate);
+    }
+
+    for failure in &report.failures {
+        println!("{}", format!("below floor: {failure}").red());
+    }
+}
+
+fn print_results(
+    sorted: &[(String, search::CmdData)],
+    bindings: &HashMap<String, String>,
+    verbose: bool,
+    window: ResultWindow,
+) {
+    if window.is_paged() {
+        // The overall top hit keeps all its examples; the rest of the page shows their best one.
+        let no_bindings = HashMap::new();
+        for (i, (cmd, data)) in window
+            .select(sorted, DEFAULT_TEXT_RESULTS)
+            .iter()
+            .enumerate()
+        {
+            let rank = window.offset + i;
+            let (show_count, bindings) = if rank == 0 {
+                (data.examples.len(), bindings)
+            } else {
+                (1, &no_bindings)
+            };
+            print_result(cmd, data, show_count, bindings, verbose);
+        }
+        if sorted.len() <= window.offset {
+            println!("No more matches.");
+        }
+        return;
+    }
+
+    for (i, (cmd, data)) in sorted.iter().enumerate().take(DEFAULT_TEXT_RESULTS) {
         let mut show_count = if i == 0 { data.examples.len() } else { 0 };
 
         // only show more than 1 command if it's exceptionally close in meaning to the top result
@@ -189,47 +626,88 @@ fn try_semantic_search(
             }
         }
 
+        let no_bindings = HashMap::new();
+        let bindings = if i == 0 { bindings } else { &no_bindings };
+        print_result(cmd, data, show_count, bindings, verbose);
+    }
+
+    if sorted.is_empty() {
+        println!("No good matches found.");
+    }
+}
+
+/// Warns under examples whose blast radius is high or worse.
+fn print_risk(syntax: &str) {
+    let assessed = risk::assess(syntax);
+    if assessed.level >= risk::RiskLevel::High {
+        println!(
+            "   {}",
+            format!(
+                "{} risk: {}",
+                assessed.level.as_str(),
+                assessed.reasons.join(", ")
+            )
+            .red()
+        );
+    }
+}
+
+fn print_result(
+    cmd: &str,
+    data: &search::CmdData,
+    show_count: usize,
+    bindings: &HashMap<String, String>,
+    verbose: bool,
+) {
+    if data.installed {
         println!("{}", cmd.bold().green());
-        if verbose {
-            let rules = if data.heuristics.is_empty() {
-                "none".to_string()
-            } else {
-                data.heuristics.join(", ")
-            };
-            println!(
-                "{}",
-                format!(
-                    "(Distance: {:.4} | Raw: {:.4} | Rules: {})",
-                    data.adjusted_score, data.raw_distance, rules
-                )
-                .bright_black()
-            );
-        }
+    } else {
+        println!(
+            "{} {}",
+            cmd.bold().green(),
+            "(not installed)".bright_black()
+        );
+    }
+    if verbose {
+        let rules = if data.heuristics.is_empty() {
+            "none".to_string()
+        } else {
+            data.heuristics.join(", ")
+        };
+        println!(
+            "{}",
+            format!(
+                "(Distance: {:.4} | Raw: {:.4} | Rules: {})",
+                data.adjusted_score, data.raw_distance, rules
+            )
+            .bright_black()
+        );
+    }
 
-        // Clean up description (strip "More information" and "See also" links)
-        let mut clean_desc = data.description.as_str();
-        if let Some(idx) = clean_desc.find(" More information:") {
-            clean_desc = &clean_desc[..idx];
-        }
-        if let Some(idx) = clean_desc.find(" See also:") {
-            clean_desc = &clean_desc[..idx];
+    println!("{}", format::clean_description(&data.description));
+
+    if show_count > 0 && !data.examples.is_empty() {
+        println!("\n{}", "Examples:".underline());
+        for example in data.examples.iter().take(show_count) {
+            println!("  {}", example.description);
+            println!("   {}", format::highlight_command(&example.syntax));
+            print_risk(&example.syntax);
+            println!();
         }
-        println!("{}", clean_desc);
 
-        if show_count > 0 && !data.examples.is_empty() {
-            println!("\n{}", "Examples:".underline());
-            for (ex_desc, ex_cmd) in data.examples.iter().take(show_count) {
-                println!("  {}", ex_desc);
-                println!("   {}", format::highlight_command(ex_cmd));
-                println!();
+        if !bindings.is_empty()
+            && let Some(rendered) = placeholder::render_best(&data.examples, bindings)
+        {
+            println!("{}", "Rendered:".underline());
+            println!("   {}", format::highlight_command(&rendered.command));
+            if !rendered.complete {
+                println!(
+                    "   {}",
+                    format!("unbound: {}", rendered.unbound.join(", ")).yellow()
+                );
             }
+            println!();
         }
-        println!();
     }
-
-    if sorted.is_empty() {
-        println!("No good matches found.");
-    }
-
-    Ok(())
+    println!();
 }
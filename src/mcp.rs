@@ -0,0 +1,507 @@
+use anyhow::{Result, bail};
+use colored::*;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::pipeline::{
+    self, DEFAULT_JSON_RESULTS, Pipeline, RankOptions, ResultWindow, SearchRequest,
+};
+use crate::risk::RiskLevel;
+use crate::{check, cli, db, explain, output, profile, search};
+
+/// Protocol revisions this server speaks, newest first. A client asking for another one
+/// is answered with the newest, as the spec requires.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC 2.0 error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Arguments of `search_command`; the same switches as `askman --json`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchParams {
+    pub query: String,
+    pub os: Option<String>,
+    #[serde(default)]
+    pub tool: Vec<String>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+    pub max_risk: Option<RiskLevel>,
+    #[serde(default)]
+    pub fill: HashMap<String, String>,
+    #[serde(default)]
+    pub installed_only: bool,
+}
+
+/// Arguments of `show_page`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageParams {
+    /// Page name; spaces are joined with `-` like `askman show`.
+    pub command: String,
+    pub os: Option<String>,
+}
+
+/// Arguments of `explain_command` and `check_command`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandLineParams {
+    pub command_line: String,
+    pub os: Option<String>,
+}
+
+/// A `tools/call` request with its arguments parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCall {
+    Search(SearchParams),
+    Show(PageParams),
+    Explain(CommandLineParams),
+    Check(CommandLineParams),
+}
+
+impl ToolCall {
+    pub fn parse(name: &str, arguments: Value) -> Result<Self> {
+        Ok(match name {
+            "search_command" => ToolCall::Search(serde_json::from_value(arguments)?),
+            "show_page" => ToolCall::Show(serde_json::from_value(arguments)?),
+            "explain_command" => ToolCall::Explain(serde_json::from_value(arguments)?),
+            "check_command" => ToolCall::Check(serde_json::from_value(arguments)?),
+            _ => bail!("unknown tool `{name}`"),
+        })
+    }
+}
+
+/// Runs tool calls. Errors are returned to the client as a failed call, not a protocol error.
+pub trait Tools {
+    fn call(&mut self, call: ToolCall) -> Result<Value>;
+}
+
+/// The `tools/list` catalogue.
+pub fn tool_definitions() -> Value {
+    let os = json!({
+        "type": "string",
+        "enum": ["linux", "osx", "windows", "freebsd", "openbsd", "netbsd"],
+        "description": "Target platform; defaults to the host OS"
+    });
+    json!([
+        {
+            "name": "search_command",
+            "description": "Find terminal command syntax for a natural-language task. Returns ranked commands with examples, confidence, intent coverage and per-example risk, exactly like `askman --json`.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "What the command should do, e.g. \"extract tar.gz\""},
+                    "os": os,
+                    "tool": {"type": "array", "items": {"type": "string"}, "description": "Only rank these tools and their subcommand pages"},
+                    "limit": {"type": "integer", "minimum": 1, "description": "Distinct commands to return"},
+                    "offset": {"type": "integer", "minimum": 0, "description": "Distinct commands to skip"},
+                    "max_risk": {"type": "string", "enum": ["low", "medium", "high", "critical"], "description": "Hide riskier examples"},
+                    "fill": {"type": "object", "additionalProperties": {"type": "string"}, "description": "Placeholder bindings for the top example"},
+                    "installed_only": {"type": "boolean", "description": "Only return tools on $PATH"}
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "show_page",
+            "description": "Print every example of a command page by exact name, e.g. `tar` or `git stash`.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command": {"type": "string"},
+                    "os": os
+                },
+                "required": ["command"]
+            }
+        },
+        {
+            "name": "explain_command",
+            "description": "Explain each command, flag and argument of an existing command line from the stored examples; tokens no example covers are listed as unexplained.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command_line": {"type": "string"},
+                    "os": os
+                },
+                "required": ["command_line"]
+            }
+        },
+        {
+            "name": "check_command",
+            "description": "Check a proposed command line before running it: `pass` when every flag appears in a stored example, `warn` when some flag does not, `unknown` when a command has no page.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "command_line": {"type": "string"},
+                    "os": os
+                },
+                "required": ["command_line"]
+            }
+        }
+    ])
+}
+
+/// Answers one JSON-RPC message. Notifications get no response.
+pub fn handle_message(message: &Value, tools: &mut impl Tools) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "expected a JSON-RPC request",
+        ));
+    };
+    // Notifications (`notifications/initialized`, `notifications/cancelled`, ...) need no reply.
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = PROTOCOL_VERSIONS
+                .into_iter()
+                .find(|v| Some(*v) == requested)
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            json!({
+                "protocolVersion": version,
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "askman", "version": env!("CARGO_PKG_VERSION")}
+            })
+        }
+        "ping" => json!({}),
+        "tools/list" => json!({"tools": tool_definitions()}),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            let call = match ToolCall::parse(name, arguments) {
+                Ok(call) => call,
+                Err(e) => return Some(error_response(id, INVALID_PARAMS, &format!("{e:#}"))),
+            };
+            match tools.call(call) {
+                Ok(output) => json!({
+                    "content": [{"type": "text", "text": pretty(&output)}],
+                    "structuredContent": output,
+                    "isError": false
+                }),
+                Err(e) => json!({
+                    "content": [{"type": "text", "text": format!("{e:#}")}],
+                    "isError": true
+                }),
+            }
+        }
+        _ => {
+            return Some(error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("method `{method}` not found"),
+            ));
+        }
+    };
+    Some(json!({"jsonrpc": "2.0", "id": id, "result": result}))
+}
+
+/// Serves newline-delimited JSON-RPC until `input` closes.
+pub fn serve(input: impl BufRead, mut output: impl Write, tools: &mut impl Tools) -> Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message, tools),
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", serde_json::to_string(&response)?)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Serves the `askman mcp` tools over stdin/stdout. The database, profile and models stay
+/// loaded for the whole session, so each call pays only for its own query.
+pub fn run_mcp(mcp_args: &cli::McpArgs) -> Result<()> {
+    let app_dir = db::get_app_dir()?;
+    let conn = db::get_connection(&db::get_db_path(&app_dir)?)?;
+    let profile = profile::load_profile()?;
+    // Every call sets its own options; these only cover the load.
+    let options = RankOptions {
+        json: true,
+        installed_only: false,
+        depth: ResultWindow::default().depth(DEFAULT_JSON_RESULTS),
+        max_risk: None,
+    };
+    let pipeline = Pipeline::load(
+        &conn,
+        &app_dir,
+        &profile,
+        mcp_args.rerank || profile.rerank.enabled,
+        options,
+    )?;
+    if let Some(reason) = &pipeline.rerank_skipped {
+        eprintln!("{}", format!("Reranking skipped: {reason}").yellow());
+    }
+
+    let mut tools = McpTools { pipeline };
+    serve(
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        &mut tools,
+    )
+}
+
+/// Answers MCP tool calls with the same JSON as the matching CLI command.
+pub struct McpTools<'a> {
+    pub pipeline: Pipeline<'a>,
+}
+
+impl Tools for McpTools<'_> {
+    fn call(&mut self, call: ToolCall) -> Result<Value> {
+        let conn = self.pipeline.conn;
+        match call {
+            ToolCall::Search(params) => self.search(params),
+            ToolCall::Show(params) => {
+                let (target_os, os_source) = search::resolve_target_os(params.os.as_deref())?;
+                let target_os = search::page_os(conn, target_os)?;
+                let command = params
+                    .command
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("-")
+                    .to_lowercase();
+                Ok(match search::lookup_page(conn, &command, target_os)? {
+                    Some(page) => serde_json::to_value(pipeline::page_output(
+                        &page,
+                        &self.pipeline.families,
+                        target_os,
+                        os_source,
+                    ))?,
+                    None => {
+                        let did_you_mean = pipeline::page_suggestions(conn, &command)?;
+                        serde_json::to_value(output::MissingPageOutput::new(
+                            &command,
+                            target_os,
+                            os_source,
+                            did_you_mean,
+                        ))?
+                    }
+                })
+            }
+            ToolCall::Explain(params) => {
+                let (target_os, os_source) = search::resolve_target_os(params.os.as_deref())?;
+                let target_os = search::page_os(conn, target_os)?;
+                let segments = explain::explain_line(&params.command_line, |command| {
+                    search::lookup_page(conn, command, target_os)
+                })?;
+                Ok(serde_json::to_value(output::ExplainOutput::new(
+                    &params.command_line,
+                    segments,
+                    target_os,
+                    os_source,
+                ))?)
+            }
+            ToolCall::Check(params) => {
+                let (target_os, os_source) = search::resolve_target_os(params.os.as_deref())?;
+                let target_os = search::page_os(conn, target_os)?;
+                let report = check::check_line(
+                    &params.command_line,
+                    |command| search::lookup_page(conn, command, target_os),
+                    |command| search::page_platforms(conn, command),
+                )?;
+                Ok(serde_json::to_value(output::CheckOutput::new(
+                    &params.command_line,
+                    &report,
+                    target_os,
+                    os_source,
+                ))?)
+            }
+        }
+    }
+}
+
+impl McpTools<'_> {
+    fn search(&mut self, params: SearchParams) -> Result<Value> {
+        let (target_os, os_source) = search::resolve_target_os(params.os.as_deref())?;
+        let target_os = search::page_os(self.pipeline.conn, target_os)?;
+        if params.limit == Some(0) {
+            anyhow::bail!("limit must be at least 1");
+        }
+        let window = ResultWindow {
+            offset: params.offset,
+            limit: params.limit,
+        };
+
+        let pipeline = &mut self.pipeline;
+        pipeline.scope = None;
+        if !params.tool.is_empty() {
+            let scope =
+                search::resolve_tool_scope(&params.tool, &pipeline.commands, &pipeline.families);
+            if !scope.unknown.is_empty() {
+                let did_you_mean = pipeline::tool_suggestions(&scope, &pipeline.commands);
+                let output = pipeline::unknown_tool_output(
+                    &params.query,
+                    target_os,
+                    os_source,
+                    &scope,
+                    did_you_mean,
+                );
+                return Ok(serde_json::to_value(output)?);
+            }
+            pipeline.scope = Some(scope);
+        }
+        pipeline.options = RankOptions {
+            json: true,
+            installed_only: params.installed_only,
+            depth: window.depth(DEFAULT_JSON_RESULTS),
+            max_risk: params.max_risk,
+        };
+
+        let outcome = pipeline.search(&params.query, target_os)?;
+        let request = SearchRequest {
+            query: &params.query,
+            target_os,
+            os_source,
+            profile: pipeline.profile,
+            tools: pipeline.scope.as_ref().map(|scope| scope.tools.as_slice()),
+            max_risk: params.max_risk,
+            window,
+            bindings: &params.fill,
+            verbose: false,
+        };
+        Ok(serde_json::to_value(pipeline::search_output(
+            &request, &outcome,
+        ))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records calls and echoes the query back.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<ToolCall>,
+    }
+
+    impl Tools for Recorder {
+        fn call(&mut self, call: ToolCall) -> Result<Value> {
+            self.calls.push(call.clone());
+            match call {
+                ToolCall::Search(params) => Ok(json!({"query": params.query})),
+                _ => bail!("database is locked"),
+            }
+        }
+    }
+
+    fn roundtrip(input: &str, tools: &mut Recorder) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, tools).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn negotiates_and_lists_tools() {
+        let mut tools = Recorder::default();
+        let responses = roundtrip(
+            concat!(
+                r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+                "\n\n",
+                r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","id":3,"method":"resources/list"}"#,
+                "\n",
+                "{not json\n",
+            ),
+            &mut tools,
+        );
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "askman");
+        let names: Vec<&str> = responses[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "search_command",
+                "show_page",
+                "explain_command",
+                "check_command"
+            ]
+        );
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[3]["id"], Value::Null);
+        assert_eq!(responses[3]["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn dispatches_tool_calls_and_reports_failures() {
+        let mut tools = Recorder::default();
+        let call = |id: u32, name: &str, arguments: Value| {
+            json!({"jsonrpc": "2.0", "id": id, "method": "tools/call",
+                   "params": {"name": name, "arguments": arguments}})
+        };
+
+        let ok = handle_message(
+            &call(
+                1,
+                "search_command",
+                json!({"query": "extract tar", "tool": ["tar"], "max_risk": "medium"}),
+            ),
+            &mut tools,
+        )
+        .unwrap();
+        assert_eq!(ok["result"]["isError"], false);
+        assert_eq!(ok["result"]["structuredContent"]["query"], "extract tar");
+        assert_eq!(
+            tools.calls[0],
+            ToolCall::Search(SearchParams {
+                query: "extract tar".to_string(),
+                tool: vec!["tar".to_string()],
+                max_risk: Some(RiskLevel::Medium),
+                ..SearchParams::default()
+            })
+        );
+
+        let failed = handle_message(
+            &call(2, "check_command", json!({"command_line": "ls -l"})),
+            &mut tools,
+        )
+        .unwrap();
+        assert_eq!(failed["result"]["isError"], true);
+        assert_eq!(failed["result"]["content"][0]["text"], "database is locked");
+
+        let unknown = handle_message(&call(3, "rm_rf", json!({})), &mut tools).unwrap();
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+        let missing =
+            handle_message(&call(4, "show_page", json!({"os": "osx"})), &mut tools).unwrap();
+        assert_eq!(missing["error"]["code"], INVALID_PARAMS);
+        assert_eq!(tools.calls.len(), 2);
+    }
+}
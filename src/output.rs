@@ -0,0 +1,465 @@
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::calibration::Calibration;
+use crate::check::{CheckReport, CheckStatus, CommandCheck};
+use crate::explain::Segment;
+use crate::format;
+use crate::fuzzy::TermCorrection;
+use crate::placeholder::{self, Parameter, Rendered};
+use crate::risk::{self, Risk, RiskLevel};
+use crate::search::{Example, OsSource, Page, TargetOs};
+
+/// Version of the JSON output below. Bumped when a field is removed, renamed or changes
+/// type; adding an optional field keeps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Everything askman prints as JSON: `--json` of each command, `askman batch` lines and the
+/// MCP tool results.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[schemars(title = "askman output")]
+#[serde(untagged)]
+pub enum Output {
+    Search(SearchOutput),
+    UnknownTool(UnknownToolOutput),
+    Page(PageOutput),
+    MissingPage(MissingPageOutput),
+    Explain(ExplainOutput),
+    Check(CheckOutput),
+    BatchError(BatchErrorOutput),
+}
+
+/// `askman --json` output. Each `askman batch` line and the MCP `search_command` tool return
+/// the same object.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SearchOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    /// The `id` of the `askman batch` line this answers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub query: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    pub profile: ProfileInfo,
+    pub rerank: RerankInfo,
+    pub calibration: Option<Calibration>,
+    /// Normalized `--tool` names, or `null` when the search was not scoped.
+    pub tools: Option<Vec<String>>,
+    pub max_risk: Option<RiskLevel>,
+    pub corrected_terms: Vec<TermCorrection>,
+    pub results: Vec<CommandResult>,
+    /// Present with `--limit` or `--offset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+    /// One entry per step of a compound question; absent otherwise.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Step>,
+}
+
+/// `askman --json --tool` output when a named tool has no page; nothing was searched.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct UnknownToolOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    /// The `id` of the `askman batch` line this answers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub query: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    pub status: UnknownToolStatus,
+    pub tools: Vec<String>,
+    pub unknown_tools: Vec<String>,
+    pub did_you_mean: Vec<String>,
+    /// Always empty.
+    #[schemars(extend("maxItems" = 0))]
+    pub results: Vec<CommandResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownToolStatus {
+    UnknownTool,
+}
+
+impl UnknownToolOutput {
+    pub fn new(
+        query: String,
+        os: TargetOs,
+        os_source: OsSource,
+        tools: Vec<String>,
+        unknown_tools: Vec<String>,
+        did_you_mean: Vec<String>,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            id: None,
+            query,
+            os,
+            os_source,
+            status: UnknownToolStatus::UnknownTool,
+            tools,
+            unknown_tools,
+            did_you_mean,
+            results: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub sources: Vec<PathBuf>,
+}
+
+/// Whether the cross-encoder was asked for and whether it ran.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RerankInfo {
+    pub enabled: bool,
+    pub applied: bool,
+    pub skipped_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CommandResult {
+    pub command: String,
+    /// The tool itself: `git` for `git-stash`, otherwise `command`.
+    pub family: String,
+    pub subcommand: Option<String>,
+    pub platform: String,
+    pub installed: bool,
+    pub description: String,
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub confidence: f64,
+    pub intent: IntentReport,
+    pub examples: Vec<ExampleOutput>,
+    /// The closest example; absent when there are no examples.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_example: Option<BestExample>,
+    /// Present when `--fill` bound a placeholder of this result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<Rendered>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<ScoreDetails>,
+}
+
+/// Ranking internals, reported with `--verbose`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ScoreDetails {
+    pub adjusted_distance: f64,
+    pub raw_distance: f64,
+    pub heuristics_applied: Vec<String>,
+    pub intent_matched_terms: Vec<String>,
+    pub intent_constraints: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct IntentReport {
+    pub coverage: f64,
+    pub status: IntentStatus,
+    pub missing_terms: Vec<String>,
+    pub violated_constraints: Vec<String>,
+}
+
+/// `pass` only when the intent is covered and any `--fill` rendering is complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentStatus {
+    Pass,
+    Warn,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ExampleOutput {
+    pub description: String,
+    pub syntax: String,
+    pub has_placeholders: bool,
+    pub parameters: Vec<Parameter>,
+    pub risk: Risk,
+    /// Distance to the query, reported with `--verbose` once the example was scored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
+}
+
+impl ExampleOutput {
+    pub fn new(example: &Example, verbose: bool) -> Self {
+        let parameters = placeholder::extract_parameters(&example.syntax);
+        Self {
+            description: example.description.replace("[", "").replace("]", ""),
+            syntax: example.syntax.clone(),
+            has_placeholders: !parameters.is_empty(),
+            parameters,
+            risk: risk::assess(&example.syntax),
+            distance: example.distance.filter(|_| verbose),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct BestExample {
+    /// Index into `examples`.
+    pub index: usize,
+    pub description: String,
+    pub syntax: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Pagination {
+    pub offset: usize,
+    pub limit: usize,
+    pub returned: usize,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Step {
+    /// 1-based position of the step in the question.
+    #[schemars(range(min = 1))]
+    pub step: usize,
+    pub query: String,
+    pub corrected_terms: Vec<TermCorrection>,
+    /// The step's top result, or `null` when nothing matched.
+    pub result: Option<CommandResult>,
+}
+
+/// `askman show --json` output and the MCP `show_page` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PageOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub command: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    #[schemars(extend("const" = true))]
+    pub found: bool,
+    pub platform: String,
+    pub installed: bool,
+    pub description: String,
+    pub examples: Vec<ExampleOutput>,
+    /// `command-*` pages on the same platforms.
+    pub variants: Vec<String>,
+}
+
+impl PageOutput {
+    pub fn new(page: &Page, os: TargetOs, os_source: OsSource, installed: bool) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command: page.command.clone(),
+            os,
+            os_source,
+            found: true,
+            platform: page.platform.clone(),
+            installed,
+            description: format::clean_description(&page.description)
+                .trim_end_matches([' ', '\n'])
+                .replace(['[', ']'], ""),
+            examples: page
+                .examples
+                .iter()
+                .map(|example| ExampleOutput::new(example, false))
+                .collect(),
+            variants: page.variants.clone(),
+        }
+    }
+}
+
+/// `askman show --json` output when the command has no page.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MissingPageOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub command: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    #[schemars(extend("const" = false))]
+    pub found: bool,
+    pub did_you_mean: Vec<String>,
+}
+
+impl MissingPageOutput {
+    pub fn new(
+        command: &str,
+        os: TargetOs,
+        os_source: OsSource,
+        did_you_mean: Vec<String>,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command: command.to_string(),
+            os,
+            os_source,
+            found: false,
+            did_you_mean,
+        }
+    }
+}
+
+/// `askman explain --json` output and the MCP `explain_command` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ExplainOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub command_line: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    pub segments: Vec<Segment>,
+    /// Tokens no stored example accounts for, across all segments.
+    pub unexplained: Vec<String>,
+}
+
+impl ExplainOutput {
+    pub fn new(line: &str, segments: Vec<Segment>, os: TargetOs, os_source: OsSource) -> Self {
+        let unexplained = segments
+            .iter()
+            .flat_map(|s| s.unexplained())
+            .map(str::to_string)
+            .collect();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command_line: line.to_string(),
+            os,
+            os_source,
+            segments,
+            unexplained,
+        }
+    }
+}
+
+/// `askman check --json` output and the MCP `check_command` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CheckOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub command_line: String,
+    pub os: TargetOs,
+    pub os_source: OsSource,
+    pub status: CheckStatus,
+    pub commands: Vec<CommandCheck>,
+    pub risk: Risk,
+}
+
+impl CheckOutput {
+    pub fn new(line: &str, report: &CheckReport, os: TargetOs, os_source: OsSource) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            command_line: line.to_string(),
+            os,
+            os_source,
+            status: report.status,
+            commands: report.commands.clone(),
+            risk: report.risk.clone(),
+        }
+    }
+}
+
+/// An `askman batch` line for a query that could not be answered.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct BatchErrorOutput {
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    /// The `id` of the input line, or `null` when the line was not valid JSON.
+    pub id: Value,
+    pub error: String,
+}
+
+impl BatchErrorOutput {
+    pub fn new(id: Value, error: &anyhow::Error) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            id,
+            error: format!("{error:#}"),
+        }
+    }
+}
+
+/// JSON Schema (draft 2020-12) of `Output`, printed by `askman schema`.
+pub fn schema() -> Value {
+    SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<Output>()
+        .to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::placeholder::ParamKind;
+
+    #[test]
+    fn example_output_hides_distance_unless_verbose() {
+        let mut example = Example::new("Extract an [a]rchive", "tar xf {{path/to/source.tar}}");
+        example.distance = Some(0.31);
+
+        let plain = ExampleOutput::new(&example, false);
+        assert_eq!(plain.description, "Extract an archive");
+        assert_eq!(plain.distance, None);
+        assert!(plain.has_placeholders);
+        assert_eq!(plain.parameters[0].kind, ParamKind::File);
+        assert_eq!(ExampleOutput::new(&example, true).distance, Some(0.31));
+    }
+
+    #[test]
+    fn schema_follows_the_serialized_shape() {
+        let schema = schema();
+        let defs = &schema["$defs"];
+        let search = &defs["SearchOutput"];
+        assert_eq!(
+            search["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+        // Skipped when empty or absent, so not required.
+        let required = search["required"].as_array().unwrap();
+        for field in ["query", "os", "results", "calibration"] {
+            assert!(
+                required.contains(&field.into()),
+                "{field} should be required"
+            );
+        }
+        for field in ["id", "pagination", "steps"] {
+            assert!(
+                !required.contains(&field.into()),
+                "{field} should be optional"
+            );
+        }
+
+        // Flattened `--verbose` scores sit next to the other result fields.
+        let result = &defs["CommandResult"]["properties"];
+        assert!(result.get("adjusted_distance").is_some());
+        assert!(result.get("scores").is_none());
+
+        assert_eq!(
+            defs["OsSource"]["enum"],
+            serde_json::json!(["detected", "flag"])
+        );
+        assert_eq!(
+            defs["UnknownToolStatus"]["enum"],
+            serde_json::json!(["unknown_tool"])
+        );
+
+        for output in [
+            "PageOutput",
+            "MissingPageOutput",
+            "ExplainOutput",
+            "CheckOutput",
+            "BatchErrorOutput",
+        ] {
+            assert_eq!(
+                defs[output]["properties"]["schema_version"]["const"], SCHEMA_VERSION,
+                "{output} should carry the schema version"
+            );
+        }
+        assert_eq!(defs["PageOutput"]["properties"]["found"]["const"], true);
+        assert_eq!(
+            defs["MissingPageOutput"]["properties"]["found"]["const"],
+            false
+        );
+        assert!(defs.get("Segment").is_some());
+    }
+}
@@ -0,0 +1,743 @@
+use anyhow::Result;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    calibration, cli, embed, format, fuzzy, installed, output, placeholder, profile, rerank, risk,
+    search,
+};
+
+/// Commands reported when neither `--limit` nor `--offset` is given.
+pub const DEFAULT_JSON_RESULTS: usize = 2;
+pub const DEFAULT_TEXT_RESULTS: usize = 3;
+
+/// Which distinct commands of a ranking to report.
+#[derive(Clone, Copy, Default)]
+pub struct ResultWindow {
+    pub offset: usize,
+    /// `None` keeps the default count and its noise filtering against the top hit.
+    pub limit: Option<usize>,
+}
+
+impl ResultWindow {
+    pub fn is_paged(&self) -> bool {
+        self.offset > 0 || self.limit.is_some()
+    }
+
+    /// The commands to report, after skipping `offset`.
+    pub fn select<'r>(
+        &self,
+        sorted: &'r [(String, search::CmdData)],
+        default_limit: usize,
+    ) -> &'r [(String, search::CmdData)] {
+        let start = self.offset.min(sorted.len());
+        let end = (start + self.limit.unwrap_or(default_limit)).min(sorted.len());
+        &sorted[start..end]
+    }
+
+    /// Distinct commands the search has to surface to fill the window and know whether more follow.
+    pub fn depth(&self, default_limit: usize) -> usize {
+        self.offset + self.limit.unwrap_or(default_limit) + 1
+    }
+}
+
+impl From<&cli::Args> for ResultWindow {
+    fn from(args: &cli::Args) -> Self {
+        Self {
+            offset: args.offset,
+            limit: args.limit.map(|n| n as usize),
+        }
+    }
+}
+
+/// Pipeline switches taken from the CLI.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct RankOptions {
+    /// Agent mode: search across platforms and hydrate a thin top hit.
+    pub json: bool,
+    pub installed_only: bool,
+    /// Distinct commands each ranking should contain.
+    pub depth: usize,
+    pub max_risk: Option<risk::RiskLevel>,
+}
+
+impl From<&cli::Args> for RankOptions {
+    fn from(args: &cli::Args) -> Self {
+        let default_limit = if args.json {
+            DEFAULT_JSON_RESULTS
+        } else {
+            DEFAULT_TEXT_RESULTS
+        };
+        Self {
+            json: args.json,
+            installed_only: args.installed_only,
+            depth: ResultWindow::from(args).depth(default_limit),
+            max_risk: args.max_risk,
+        }
+    }
+}
+
+pub type Ranked = Vec<(String, search::CmdData)>;
+
+/// Models and lookup tables loaded once and shared by every query of a run.
+pub struct Pipeline<'a> {
+    pub conn: &'a rusqlite::Connection,
+    pub profile: &'a profile::RankingProfile,
+    pub options: RankOptions,
+    pub calibration: Option<calibration::Calibration>,
+    pub embedder: fastembed::TextEmbedding,
+    /// Reranking was asked for; `reranker` stays `None` when it could not be loaded.
+    pub rerank: bool,
+    pub reranker: Option<fastembed::TextRerank>,
+    pub rerank_skipped: Option<String>,
+    /// Known command names, for typo correction.
+    pub commands: Vec<String>,
+    pub families: search::CommandFamilies,
+    /// Set by `--tool`; every query of the run stays inside it.
+    pub scope: Option<search::ToolScope>,
+    /// `$PATH` that `installed` is resolved against; `None` uses this process's own.
+    pub path: Option<std::ffi::OsString>,
+}
+
+/// Outcome of one ranked lookup.
+#[derive(Serialize, Deserialize)]
+pub struct Ranking {
+    /// The query after typo correction; intent coverage is measured against this.
+    pub query: String,
+    pub corrections: Vec<fuzzy::TermCorrection>,
+    pub results: Ranked,
+}
+
+/// Rankings for one query and how they were produced, in-process or by `askman daemon`.
+#[derive(Serialize, Deserialize)]
+pub struct SearchOutcome {
+    /// Has no results for a compound question outside `--json`, where only the steps are shown.
+    pub ranking: Ranking,
+    /// One ranking per step of a compound question; empty otherwise.
+    pub steps: Vec<Ranking>,
+    pub rerank: output::RerankInfo,
+    pub calibration: Option<calibration::Calibration>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn load(
+        conn: &'a rusqlite::Connection,
+        app_dir: &std::path::Path,
+        profile: &'a profile::RankingProfile,
+        rerank: bool,
+        options: RankOptions,
+    ) -> Result<Self> {
+        let embedder = embed::init_model(app_dir)?;
+        let (reranker, rerank_skipped) = load_reranker(app_dir, rerank);
+        Ok(Self {
+            conn,
+            profile,
+            options,
+            calibration: load_calibration(profile),
+            embedder,
+            rerank,
+            reranker,
+            rerank_skipped,
+            commands: search::load_command_names(conn)?,
+            families: search::load_command_families(conn)?,
+            scope: None,
+            path: None,
+        })
+    }
+
+    pub fn with_scope(mut self, scope: Option<search::ToolScope>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Ranks a query and, for a compound question, each of its steps. Text output shows only
+    /// the steps of a compound question, so there the question as a whole is not ranked.
+    pub fn search(&self, query: &str, target_os: search::TargetOs) -> Result<SearchOutcome> {
+        let corrections = self.corrections(query)?;
+        let corrected = fuzzy::apply_corrections(query, &corrections);
+        if !self.options.json && search::decompose_query(&corrected).len() >= 2 {
+            let ranking = Ranking {
+                query: corrected,
+                corrections,
+                results: Vec::new(),
+            };
+            let steps = self.rank_steps(&ranking, target_os)?;
+            return Ok(self.outcome(ranking, steps));
+        }
+        let q_vec = embed::embed_query(&self.embedder, &corrected)?;
+        self.search_embedded(query, corrections, &q_vec, target_os)
+    }
+
+    /// `search` with the corrected query already embedded, e.g. as part of a batch.
+    pub fn search_embedded(
+        &self,
+        query: &str,
+        corrections: Vec<fuzzy::TermCorrection>,
+        q_vec: &[f32],
+        target_os: search::TargetOs,
+    ) -> Result<SearchOutcome> {
+        let ranking = self.rank_embedded(query, corrections, q_vec, target_os)?;
+        let steps = self.rank_steps(&ranking, target_os)?;
+        Ok(self.outcome(ranking, steps))
+    }
+
+    fn outcome(&self, ranking: Ranking, steps: Vec<Ranking>) -> SearchOutcome {
+        SearchOutcome {
+            ranking,
+            steps,
+            rerank: output::RerankInfo {
+                enabled: self.rerank,
+                applied: self.rerank && self.reranker.is_some(),
+                skipped_reason: self.rerank_skipped.clone().filter(|_| self.rerank),
+            },
+            calibration: self.calibration.clone(),
+        }
+    }
+
+    /// Compound questions also get one ranked lookup per step, in order; a single-intent
+    /// query has no steps. The steps are embedded in one model call.
+    fn rank_steps(&self, ranking: &Ranking, target_os: search::TargetOs) -> Result<Vec<Ranking>> {
+        let sub_queries = search::decompose_query(&ranking.query);
+        if sub_queries.len() < 2 {
+            return Ok(Vec::new());
+        }
+        let corrections = sub_queries
+            .iter()
+            .map(|sub_query| self.corrections(sub_query))
+            .collect::<Result<Vec<_>>>()?;
+        let corrected: Vec<String> = sub_queries
+            .iter()
+            .zip(&corrections)
+            .map(|(sub_query, corrections)| fuzzy::apply_corrections(sub_query, corrections))
+            .collect();
+        let texts: Vec<&str> = corrected.iter().map(String::as_str).collect();
+        let q_vecs = embed::embed_queries(&self.embedder, &texts)?;
+        sub_queries
+            .iter()
+            .zip(corrections)
+            .zip(&q_vecs)
+            .map(|((sub_query, corrections), q_vec)| {
+                self.rank_embedded(sub_query, corrections, q_vec, target_os)
+            })
+            .collect()
+    }
+
+    /// Runs the full ranking pipeline for one query: typo correction, KNN + lexical fusion,
+    /// PATH annotation, optional reranking, and (in JSON mode) hydration of a thin top hit.
+    pub fn rank(&self, query: &str, target_os: search::TargetOs) -> Result<Ranking> {
+        let corrections = self.corrections(query)?;
+        let q_vec = embed::embed_query(
+            &self.embedder,
+            &fuzzy::apply_corrections(query, &corrections),
+        )?;
+        self.rank_embedded(query, corrections, &q_vec, target_os)
+    }
+
+    /// Query tokens taken to be misspelled command names; the query is embedded with them fixed.
+    pub fn corrections(&self, query: &str) -> Result<Vec<fuzzy::TermCorrection>> {
+        search::correct_query_terms(self.conn, query, &self.commands)
+    }
+
+    /// `rank` with the corrected query already embedded as `q_vec`.
+    pub fn rank_embedded(
+        &self,
+        query: &str,
+        corrections: Vec<fuzzy::TermCorrection>,
+        q_vec: &[f32],
+        target_os: search::TargetOs,
+    ) -> Result<Ranking> {
+        let (conn, profile) = (self.conn, self.profile);
+        let context = search::QueryContext {
+            corrections,
+            families: &self.families,
+            scope: self.scope.as_ref(),
+            min_commands: self.options.depth,
+        };
+        let corrected = fuzzy::apply_corrections(query, &context.corrections);
+
+        let mut sorted = search::perform_search(
+            conn,
+            query,
+            q_vec,
+            target_os,
+            self.options.json,
+            profile,
+            &context,
+        )?;
+        search::annotate_installed(
+            &mut sorted,
+            profile,
+            self.options.installed_only,
+            |cmd, family| match &self.path {
+                Some(path) => installed::is_installed_in(cmd, family, path),
+                None => installed::is_installed(cmd, family),
+            },
+        );
+        if self.rerank
+            && let Some(reranker) = &self.reranker
+        {
+            rerank::rerank_results(reranker, &corrected, &mut sorted, profile.rerank.top_k)?;
+        }
+
+        if self.options.json {
+            // JSON policy blocks thin complex results; hydrate the top hit with more examples
+            // so strong single-intent queries are less likely to be rejected as under-specified.
+            // Follow-up after exercising this: ensure hydration respects the requested OS or tags each example with its platform.
+            let _ = search::hydrate_top_result_examples(
+                conn,
+                &mut sorted,
+                &corrected,
+                target_os,
+                true,
+                search::HYDRATE_MIN_EXAMPLES,
+                search::HYDRATE_MAX_EXAMPLES,
+            )?;
+            // Hydrated examples arrive unscored; rank them against the query with the rest.
+            if let Some((cmd, data)) = sorted.first_mut() {
+                search::score_examples(conn, cmd, data, q_vec)?;
+            }
+        }
+        if let Some(max_risk) = self.options.max_risk {
+            risk::hide_above(&mut sorted, max_risk);
+        }
+
+        Ok(Ranking {
+            query: corrected,
+            corrections: context.corrections,
+            results: sorted,
+        })
+    }
+}
+
+/// What one search reports, wherever the query came from.
+#[derive(Clone, Copy)]
+pub struct SearchRequest<'r> {
+    pub query: &'r str,
+    pub target_os: search::TargetOs,
+    pub os_source: search::OsSource,
+    pub profile: &'r profile::RankingProfile,
+    /// Normalized `--tool` names, when the search was scoped.
+    pub tools: Option<&'r [String]>,
+    pub max_risk: Option<risk::RiskLevel>,
+    pub window: ResultWindow,
+    pub bindings: &'r HashMap<String, String>,
+    pub verbose: bool,
+}
+
+/// The `--json` search output, shared by the CLI, `askman mcp` and `askman daemon` clients.
+pub fn search_output(request: &SearchRequest, outcome: &SearchOutcome) -> output::SearchOutput {
+    let SearchRequest {
+        query,
+        target_os,
+        os_source,
+        profile,
+        tools,
+        max_risk,
+        window,
+        bindings,
+        verbose,
+    } = *request;
+    let (ranking, steps) = (&outcome.ranking, &outcome.steps);
+    let calibration = outcome.calibration.as_ref();
+
+    let pagination = window.is_paged().then(|| {
+        let returned = window.select(&ranking.results, DEFAULT_JSON_RESULTS).len();
+        output::Pagination {
+            offset: window.offset,
+            limit: window.limit.unwrap_or(DEFAULT_JSON_RESULTS),
+            returned,
+            has_more: ranking.results.len() > window.offset + returned,
+        }
+    });
+
+    let steps = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| output::Step {
+            step: i + 1,
+            query: step.query.clone(),
+            corrected_terms: step.corrections.clone(),
+            result: step.results.first().map(|(cmd, data)| {
+                let confidence = confidence(&step.query, cmd, data, profile, calibration);
+                command_result(
+                    &step.query,
+                    cmd,
+                    data,
+                    confidence,
+                    profile,
+                    bindings,
+                    verbose,
+                )
+            }),
+        })
+        .collect();
+
+    output::SearchOutput {
+        schema_version: output::SCHEMA_VERSION,
+        id: None,
+        query: query.to_string(),
+        os: target_os,
+        os_source,
+        profile: output::ProfileInfo {
+            name: profile.name.clone(),
+            sources: profile.sources.clone(),
+        },
+        rerank: outcome.rerank.clone(),
+        calibration: calibration.cloned(),
+        tools: tools.map(<[String]>::to_vec),
+        max_risk,
+        corrected_terms: ranking.corrections.clone(),
+        results: command_results(
+            &ranking.query,
+            &ranking.results,
+            profile,
+            calibration,
+            bindings,
+            verbose,
+            window,
+        ),
+        pagination,
+        steps,
+    }
+}
+
+/// The stored fit for the active profile. A fit from other ranking settings is ignored,
+/// with a warning, since its distances no longer mean the same thing; so is an unreadable store.
+pub fn load_calibration(profile: &profile::RankingProfile) -> Option<calibration::Calibration> {
+    let path = calibration::store_path()?;
+    let warning = match calibration::load(&path, profile) {
+        Ok(calibration::Stored::Fitted(fit)) => return Some(fit),
+        Ok(calibration::Stored::Missing) => return None,
+        Ok(calibration::Stored::Stale) => format!(
+            "(Calibration for profile `{}` was fitted with other ranking settings; rerun `askman calibrate`)",
+            profile.name
+        ),
+        // A broken store must not take every search down with it; `calibrate` rewrites it.
+        Err(e) => format!("(Ignoring calibration: {e:#}; rerun `askman calibrate`)"),
+    };
+    eprintln!("{}", warning.yellow());
+    None
+}
+
+/// The reranker is opt-in and never downloaded implicitly; fall back to the base ranking.
+pub fn load_reranker(
+    app_dir: &std::path::Path,
+    enabled: bool,
+) -> (Option<fastembed::TextRerank>, Option<String>) {
+    if !enabled {
+        return (None, None);
+    }
+    match rerank::load_cached_reranker(app_dir) {
+        Ok(Some(reranker)) => (Some(reranker), None),
+        Ok(None) => (
+            None,
+            Some("reranker model not cached; run `askman download-reranker`".to_string()),
+        ),
+        Err(e) => (None, Some(format!("{e:#}"))),
+    }
+}
+
+pub fn tool_suggestions(scope: &search::ToolScope, commands: &[String]) -> Vec<String> {
+    scope
+        .unknown
+        .iter()
+        .flat_map(|tool| fuzzy::correct_terms(tool, commands, |_| false))
+        .map(|c| c.corrected)
+        .collect()
+}
+
+pub fn unknown_tool_output(
+    query: &str,
+    target_os: search::TargetOs,
+    os_source: search::OsSource,
+    scope: &search::ToolScope,
+    did_you_mean: Vec<String>,
+) -> output::UnknownToolOutput {
+    output::UnknownToolOutput::new(
+        query.to_string(),
+        target_os,
+        os_source,
+        scope.tools.clone(),
+        scope.unknown.clone(),
+        did_you_mean,
+    )
+}
+
+pub fn page_suggestions(conn: &rusqlite::Connection, command: &str) -> Result<Vec<String>> {
+    let known = search::load_command_names(conn)?;
+    Ok(fuzzy::correct_terms(command, &known, |_| false)
+        .into_iter()
+        .map(|c| c.corrected)
+        .collect())
+}
+
+/// Whether the page's tool resolves on `$PATH`; a subcommand page falls back to its family's tool.
+pub fn page_installed(page: &search::Page, families: &search::CommandFamilies) -> bool {
+    let family = families.get(&page.command).map(|f| f.family.as_str());
+    installed::is_installed(&page.command, family)
+}
+
+/// `installed` is about this machine, so it is filled in here rather than by `output`.
+pub fn page_output(
+    page: &search::Page,
+    families: &search::CommandFamilies,
+    target_os: search::TargetOs,
+    os_source: search::OsSource,
+) -> output::PageOutput {
+    let installed = page_installed(page, families);
+    output::PageOutput::new(page, target_os, os_source, installed)
+}
+
+// confidence for standard LLM agents:
+// a calibration fitted by `askman calibrate` maps distance + intent coverage to the
+// probability of a correct hit; without one, fall back to the fixed 1 - ratio^7 curve
+fn confidence(
+    query: &str,
+    cmd: &str,
+    data: &search::CmdData,
+    profile: &profile::RankingProfile,
+    calibration: Option<&calibration::Calibration>,
+) -> f64 {
+    match calibration {
+        Some(calibration) => {
+            let coverage = search::evaluate_intent_coverage(query, cmd, data, profile).score;
+            calibration.probability(data.adjusted_score, coverage)
+        }
+        None => calibration::curve_confidence(data.adjusted_score, profile.max_distance),
+    }
+}
+
+fn command_results(
+    query: &str,
+    sorted: &[(String, search::CmdData)],
+    profile: &profile::RankingProfile,
+    calibration: Option<&calibration::Calibration>,
+    bindings: &HashMap<String, String>,
+    verbose: bool,
+    window: ResultWindow,
+) -> Vec<output::CommandResult> {
+    if window.is_paged() {
+        // An explicit page is returned as asked; the noise filter below only shapes the default.
+        let no_bindings = HashMap::new();
+        return window
+            .select(sorted, DEFAULT_JSON_RESULTS)
+            .iter()
+            .enumerate()
+            .map(|(i, (cmd, data))| {
+                let confidence = confidence(query, cmd, data, profile, calibration);
+                let bindings = if window.offset + i == 0 {
+                    bindings
+                } else {
+                    &no_bindings
+                };
+                command_result(query, cmd, data, confidence, profile, bindings, verbose)
+            })
+            .collect();
+    }
+
+    let mut results = Vec::new();
+    for (i, (cmd, data)) in sorted.iter().enumerate().take(DEFAULT_JSON_RESULTS) {
+        let confidence = confidence(query, cmd, data, profile, calibration);
+
+        // noise reduction:
+        // If we are not at least 50% confident, avoid it.
+        // If the absolute best result (#1) is a slam dunk (> 90%), and this result
+        // is a distant second (trailing by > 10%), also avoid it.
+        if i > 0 {
+            if confidence < 0.50 {
+                break;
+            }
+
+            // Need to compute the #1 result's confidence for the delta check
+            let (top_cmd, top_data) = &sorted[0];
+            let top_confidence = self::confidence(query, top_cmd, top_data, profile, calibration);
+
+            if top_confidence > 0.90 && (top_confidence - confidence) > 0.10 {
+                break;
+            }
+        }
+
+        results.push(command_result(
+            query, cmd, data, confidence, profile, bindings, verbose,
+        ));
+    }
+    results
+}
+
+fn command_result(
+    query: &str,
+    cmd: &str,
+    data: &search::CmdData,
+    confidence: f64,
+    profile: &profile::RankingProfile,
+    bindings: &HashMap<String, String>,
+    verbose: bool,
+) -> output::CommandResult {
+    // Expose partial-intent mismatches directly to agents (`pass`/`warn` + missing terms).
+    let intent = search::evaluate_intent_coverage(query, cmd, data, profile);
+
+    // A half-filled command is not executable; never let it pass the intent gate.
+    let rendered = if bindings.is_empty() {
+        None
+    } else {
+        placeholder::render_best(&data.examples, bindings)
+    };
+    let status = if intent.strong && rendered.as_ref().is_none_or(|r| r.complete) {
+        output::IntentStatus::Pass
+    } else {
+        output::IntentStatus::Warn
+    };
+
+    // Examples are sorted by their own distance, so this is normally the first one.
+    let best_index = data
+        .examples
+        .iter()
+        .enumerate()
+        .filter_map(|(i, example)| example.distance.map(|d| (i, d)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i);
+    let best_example = data
+        .examples
+        .get(best_index)
+        .map(|best| output::BestExample {
+            index: best_index,
+            description: best.description.replace("[", "").replace("]", ""),
+            syntax: best.syntax.clone(),
+        });
+
+    let scores = verbose.then(|| output::ScoreDetails {
+        adjusted_distance: data.adjusted_score,
+        raw_distance: data.raw_distance,
+        heuristics_applied: data.heuristics.clone(),
+        intent_matched_terms: intent.matched_terms.clone(),
+        intent_constraints: intent.constraints.clone(),
+        rerank_score: data.rerank_score,
+    });
+
+    output::CommandResult {
+        command: cmd.to_string(),
+        family: data
+            .family
+            .as_ref()
+            .map_or(cmd, |f| f.family.as_str())
+            .to_string(),
+        subcommand: data.family.as_ref().map(|f| f.subcommand.clone()),
+        platform: data.platform.clone(),
+        installed: data.installed,
+        description: format::clean_description(&data.description)
+            .trim_end_matches([' ', '\n'])
+            .replace("[", "")
+            .replace("]", ""),
+        confidence: (confidence * 10000.0).round() / 10000.0,
+        intent: output::IntentReport {
+            coverage: (intent.score * 10000.0).round() / 10000.0,
+            status,
+            missing_terms: intent.missing_terms,
+            violated_constraints: intent.violated_constraints,
+        },
+        examples: data
+            .examples
+            .iter()
+            .map(|example| output::ExampleOutput::new(example, verbose))
+            .collect(),
+        best_example,
+        rendered,
+        scores,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{CmdData, Example};
+
+    fn outcome(commands: &[&str]) -> SearchOutcome {
+        let results = commands
+            .iter()
+            .enumerate()
+            .map(|(i, cmd)| {
+                let example = Example::new("Run it", format!("{cmd} {{{{path/to/file}}}}"));
+                let data = CmdData::fixture("A tool.", vec![example], 0.1 * (i + 1) as f64);
+                (cmd.to_string(), data)
+            })
+            .collect();
+        SearchOutcome {
+            ranking: Ranking {
+                query: "run a tool".to_string(),
+                corrections: vec![],
+                results,
+            },
+            steps: vec![],
+            rerank: output::RerankInfo {
+                enabled: false,
+                applied: false,
+                skipped_reason: None,
+            },
+            calibration: None,
+        }
+    }
+
+    #[test]
+    fn search_output_reports_the_requested_page() {
+        let profile = profile::RankingProfile::default();
+        let bindings = HashMap::new();
+        let request = SearchRequest {
+            query: "run a tool",
+            target_os: search::TargetOs::Linux,
+            os_source: search::OsSource::Flag,
+            profile: &profile,
+            tools: None,
+            max_risk: None,
+            window: ResultWindow {
+                offset: 1,
+                limit: Some(1),
+            },
+            bindings: &bindings,
+            verbose: false,
+        };
+        let output = search_output(&request, &outcome(&["cat", "bat", "tac"]));
+
+        assert_eq!(output.results.len(), 1);
+        assert_eq!(output.results[0].command, "bat");
+        let pagination = output.pagination.unwrap();
+        assert_eq!(pagination.returned, 1);
+        assert!(pagination.has_more);
+        assert_eq!(request.window.depth(DEFAULT_JSON_RESULTS), 3);
+    }
+
+    #[test]
+    fn best_example_points_at_the_closest_example() {
+        let profile = profile::RankingProfile::default();
+        let mut far = Example::new("Print a file", "cat {{path/to/file}}");
+        far.distance = Some(0.6);
+        let mut near = Example::new("Number lines", "cat -n {{path/to/file}}");
+        near.distance = Some(0.2);
+        let data = CmdData::fixture("Print files.", vec![far, near], 0.2);
+
+        let result = command_result(
+            "number lines",
+            "cat",
+            &data,
+            0.9,
+            &profile,
+            &HashMap::new(),
+            false,
+        );
+        let best = result.best_example.unwrap();
+        assert_eq!(best.index, 1);
+        assert_eq!(result.examples[best.index].syntax, best.syntax);
+
+        let unscored = CmdData::fixture("Print files.", vec![Example::new("Print", "cat")], 0.2);
+        let result = command_result(
+            "print",
+            "cat",
+            &unscored,
+            0.9,
+            &profile,
+            &HashMap::new(),
+            false,
+        );
+        assert_eq!(result.best_example.unwrap().index, 0);
+    }
+}
@@ -0,0 +1,381 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::format::{CommandToken, tokenize_command};
+use crate::search::Example;
+
+/// Best-effort type of a tldr `{{...}}` argument, inferred from its wording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamKind {
+    /// Alternate option spellings such as `{{[-f|--force]}}`.
+    Flag,
+    Url,
+    Port,
+    Integer,
+    File,
+    Path,
+    String,
+}
+
+/// A placeholder in an example command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Parameter {
+    /// Identifier used to bind a value (e.g. `source_tar` for `{{path/to/source.tar}}`).
+    pub name: String,
+    /// The placeholder exactly as written, braces included.
+    pub raw: String,
+    /// Byte offset of `raw` within the example syntax.
+    pub position: usize,
+    pub kind: ParamKind,
+}
+
+/// Lists every `{{...}}` placeholder in an example command, in order of appearance.
+/// Repeats of the same placeholder share a name; different placeholders that would
+/// collapse to the same name get a numeric suffix (`file`, `file_2`).
+pub fn extract_parameters(syntax: &str) -> Vec<Parameter> {
+    let mut params: Vec<Parameter> = Vec::new();
+
+    for token in tokenize_command(syntax) {
+        let CommandToken::Placeholder { inner, offset } = token else {
+            continue;
+        };
+
+        let raw = format!("{{{{{inner}}}}}");
+        let raw = if syntax[offset..].starts_with(&raw) {
+            raw
+        } else {
+            // Unterminated placeholder at the end of the line.
+            syntax[offset..].to_string()
+        };
+
+        let name = match params.iter().find(|p| p.raw == raw) {
+            Some(existing) => existing.name.clone(),
+            None => unique_name(&params, parameter_name(&inner)),
+        };
+
+        params.push(Parameter {
+            name,
+            raw,
+            position: offset,
+            kind: infer_kind(&inner),
+        });
+    }
+
+    params
+}
+
+fn unique_name(params: &[Parameter], base: String) -> String {
+    if !params.iter().any(|p| p.name == base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{base}_{n}"))
+        .find(|candidate| !params.iter().any(|p| &p.name == candidate))
+        .unwrap_or(base)
+}
+
+/// Derives a short identifier from placeholder text:
+/// `path/to/source.tar` -> `source_tar`, `[-f|--force]` -> `force`, `user@host` -> `user_host`.
+pub fn parameter_name(inner: &str) -> String {
+    let inner = inner.trim();
+    let base = if is_flag_alternates(inner) {
+        long_form(inner)
+    } else {
+        inner
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(inner)
+    };
+
+    let mut name = String::new();
+    for c in base.chars() {
+        if c.is_alphanumeric() {
+            name.extend(c.to_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let name = name.trim_end_matches('_').to_string();
+
+    if name.is_empty() {
+        "arg".to_string()
+    } else {
+        name
+    }
+}
+
+/// The longest spelling of `[-f|--force]`, i.e. `--force`.
+fn long_form(inner: &str) -> &str {
+    inner
+        .trim_matches(|c| c == '[' || c == ']')
+        .split('|')
+        .max_by_key(|alt| alt.len())
+        .unwrap_or(inner)
+}
+
+fn is_flag_alternates(inner: &str) -> bool {
+    inner.starts_with('[') && inner.ends_with(']') && inner.contains('-')
+}
+
+pub fn infer_kind(inner: &str) -> ParamKind {
+    let lower = inner.trim().to_lowercase();
+
+    if is_flag_alternates(&lower) {
+        return ParamKind::Flag;
+    }
+    if lower.contains("://") || lower.contains("url") || lower.contains("uri") {
+        return ParamKind::Url;
+    }
+    if lower.contains("port") {
+        return ParamKind::Port;
+    }
+    if lower.chars().all(|c| c.is_ascii_digit())
+        || ["number", "count", "seconds", "minutes", "size", "amount"]
+            .iter()
+            .any(|w| lower.contains(w))
+    {
+        return ParamKind::Integer;
+    }
+
+    let last = lower.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let is_directory = lower.ends_with('/') || last.contains("dir") || last.contains("folder");
+    if lower.contains("path/to/") || is_directory {
+        if !is_directory && (last.contains('.') || last.contains("file")) {
+            return ParamKind::File;
+        }
+        return ParamKind::Path;
+    }
+    if lower.contains("file") {
+        return ParamKind::File;
+    }
+
+    ParamKind::String
+}
+
+/// An example with `--fill` bindings substituted in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Rendered {
+    /// Index of the example within the result's `examples`.
+    pub example: usize,
+    pub command: String,
+    pub bound: Vec<String>,
+    /// Placeholder names left as `{{...}}` in `command`; non-empty means not executable as-is.
+    pub unbound: Vec<String>,
+    pub complete: bool,
+}
+
+/// Parses a `name=value` binding from the command line.
+pub fn parse_binding(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected name=value, got `{s}`")),
+    }
+}
+
+/// Picks the example that uses the most bound names and renders it.
+/// Ties go to the earliest example, which is the most relevant one once examples are sorted.
+pub fn render_best(examples: &[Example], bindings: &HashMap<String, String>) -> Option<Rendered> {
+    let mut best: Option<(usize, usize)> = None;
+    for (i, Example { syntax, .. }) in examples.iter().enumerate() {
+        let mut names: Vec<String> = extract_parameters(syntax)
+            .into_iter()
+            .map(|p| p.name)
+            .filter(|n| bindings.contains_key(n))
+            .collect();
+        names.sort();
+        names.dedup();
+        if best.is_none_or(|(_, hits)| names.len() > hits) {
+            best = Some((i, names.len()));
+        }
+    }
+
+    let (index, _) = best?;
+    Some(render(index, &examples[index].syntax, bindings))
+}
+
+/// Substitutes bound placeholders with shell-quoted values; unbound ones stay as written.
+/// Unbound flag alternates need no value, so they are written in their long form.
+pub fn render(example: usize, syntax: &str, bindings: &HashMap<String, String>) -> Rendered {
+    let mut command = String::new();
+    let mut bound = Vec::new();
+    let mut unbound = Vec::new();
+    let mut cursor = 0;
+
+    for param in extract_parameters(syntax) {
+        command.push_str(&syntax[cursor..param.position]);
+        match bindings.get(&param.name) {
+            Some(value) => {
+                command.push_str(&shell_quote(value));
+                if !bound.contains(&param.name) {
+                    bound.push(param.name);
+                }
+            }
+            None if param.kind == ParamKind::Flag => {
+                let inner = param.raw.trim_start_matches("{{").trim_end_matches("}}");
+                command.push_str(long_form(inner.trim()));
+            }
+            None => {
+                command.push_str(&param.raw);
+                if !unbound.contains(&param.name) {
+                    unbound.push(param.name);
+                }
+            }
+        }
+        cursor = param.position + param.raw.len();
+    }
+    command.push_str(&syntax[cursor..]);
+
+    Rendered {
+        example,
+        command,
+        complete: unbound.is_empty(),
+        bound,
+        unbound,
+    }
+}
+
+/// POSIX single-quoting for values that contain whitespace or shell metacharacters.
+pub fn shell_quote(value: &str) -> String {
+    let is_safe = |c: char| c.is_alphanumeric() || "-_./:@%+=,".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_names_positions_and_kinds() {
+        let params = extract_parameters("tar xf {{path/to/source.tar}} -C {{path/to/directory}}");
+        assert_eq!(params.len(), 2);
+
+        assert_eq!(params[0].name, "source_tar");
+        assert_eq!(params[0].raw, "{{path/to/source.tar}}");
+        assert_eq!(params[0].position, 7);
+        assert_eq!(params[0].kind, ParamKind::File);
+
+        assert_eq!(params[1].name, "directory");
+        assert_eq!(params[1].kind, ParamKind::Path);
+    }
+
+    #[test]
+    fn infers_kinds_from_wording() {
+        assert_eq!(infer_kind("[-f|--force]"), ParamKind::Flag);
+        assert_eq!(infer_kind("https://example.com"), ParamKind::Url);
+        assert_eq!(infer_kind("port"), ParamKind::Port);
+        assert_eq!(infer_kind("1080"), ParamKind::Integer);
+        assert_eq!(infer_kind("number_of_lines"), ParamKind::Integer);
+        assert_eq!(infer_kind("path/to/file"), ParamKind::File);
+        assert_eq!(infer_kind("file"), ParamKind::File);
+        assert_eq!(infer_kind("path/to/"), ParamKind::Path);
+        assert_eq!(infer_kind("user@host"), ParamKind::String);
+    }
+
+    #[test]
+    fn repeated_placeholders_share_a_name() {
+        let params = extract_parameters("cp {{file}} {{file}}.bak && cat {{path/to/file}}");
+        let names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["file", "file", "file_2"]);
+    }
+
+    #[test]
+    fn flag_alternates_use_long_form_name() {
+        let params = extract_parameters("rm {{[-r|--recursive]}} {{path/to/dir}}");
+        assert_eq!(params[0].name, "recursive");
+        assert_eq!(params[0].kind, ParamKind::Flag);
+    }
+
+    #[test]
+    fn no_placeholders_yields_empty_list() {
+        assert!(extract_parameters("ls -la").is_empty());
+    }
+
+    fn bindings(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_bindings() {
+        assert_eq!(
+            parse_binding("source_tar=a b.tar"),
+            Ok(("source_tar".to_string(), "a b.tar".to_string()))
+        );
+        assert_eq!(
+            parse_binding("empty="),
+            Ok(("empty".to_string(), String::new()))
+        );
+        assert!(parse_binding("novalue").is_err());
+        assert!(parse_binding("=value").is_err());
+    }
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(shell_quote("backup.tar"), "backup.tar");
+        assert_eq!(shell_quote("user@host:/srv"), "user@host:/srv");
+        assert_eq!(shell_quote("my file.txt"), "'my file.txt'");
+        assert_eq!(shell_quote("$(rm -rf ~)"), "'$(rm -rf ~)'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn renders_bound_and_marks_unbound() {
+        let rendered = render(
+            0,
+            "tar xf {{path/to/source.tar}} -C {{path/to/directory}}",
+            &bindings(&[("source_tar", "my backup.tar")]),
+        );
+        assert_eq!(
+            rendered.command,
+            "tar xf 'my backup.tar' -C {{path/to/directory}}"
+        );
+        assert_eq!(rendered.bound, vec!["source_tar"]);
+        assert_eq!(rendered.unbound, vec!["directory"]);
+        assert!(!rendered.complete);
+    }
+
+    #[test]
+    fn flag_alternates_render_as_long_form() {
+        let rendered = render(
+            0,
+            "rm {{[-r|--recursive]}} {{path/to/directory}}",
+            &bindings(&[("directory", "old logs")]),
+        );
+        assert_eq!(rendered.command, "rm --recursive 'old logs'");
+        assert_eq!(rendered.bound, vec!["directory"]);
+        assert!(rendered.unbound.is_empty());
+        assert!(rendered.complete);
+    }
+
+    #[test]
+    fn render_best_prefers_example_using_most_bindings() {
+        let examples = vec![
+            Example::new("List", "tar tf {{path/to/source.tar}}"),
+            Example::new(
+                "Extract to dir",
+                "tar xf {{path/to/source.tar}} -C {{path/to/directory}}",
+            ),
+        ];
+        let rendered = render_best(
+            &examples,
+            &bindings(&[("source_tar", "a.tar"), ("directory", "/tmp")]),
+        )
+        .unwrap();
+        assert_eq!(rendered.example, 1);
+        assert_eq!(rendered.command, "tar xf a.tar -C /tmp");
+        assert!(rendered.complete);
+
+        assert!(render_best(&[], &bindings(&[])).is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::search;
+
+const PROFILE_FILE: &str = "ranking.toml";
+
+/// Multipliers applied to the raw distance by `search::adjust_score` (lower distance ranks higher).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingWeights {
+    pub official_site: f64,
+    pub exact_match: f64,
+    /// Best case for a query token that is a near-miss of the command name; graded toward 1.0.
+    pub fuzzy_match: f64,
+    pub core_command: f64,
+    pub niche_variant: f64,
+    /// Folds the RRF score into the distance; see `search::fusion_weight`.
+    pub lexical_match: f64,
+    /// Applied to tools found on `$PATH`. 1.0 (off) keeps rankings identical across machines.
+    pub installed: f64,
+}
+
+/// Coverage thresholds used by `search::evaluate_intent_coverage`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntentThresholds {
+    pub complex_min_terms: usize,
+    pub min_score_simple: f64,
+    pub min_score_complex: f64,
+}
+
+/// Optional cross-encoder pass over the top of the ranking (see `rerank`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RerankSettings {
+    pub enabled: bool,
+    /// Number of top commands whose examples are rescored.
+    pub top_k: usize,
+}
+
+/// Every ranking knob in one place. `Default` reproduces the built-in behavior exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingProfile {
+    pub name: String,
+    /// Files that contributed to this profile, in the order they were applied.
+    pub sources: Vec<PathBuf>,
+    pub core_commands: Vec<String>,
+    pub official_sites: Vec<String>,
+    pub max_distance: f64,
+    pub weights: RankingWeights,
+    pub intent: IntentThresholds,
+    pub rerank: RerankSettings,
+}
+
+impl Default for RankingProfile {
+    fn default() -> Self {
+        Self {
+            name: "builtin".to_string(),
+            sources: vec![],
+            core_commands: search::CORE_COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            official_sites: search::OFFICIAL_SITES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            max_distance: search::MAX_DISTANCE,
+            weights: RankingWeights {
+                official_site: 0.8,
+                exact_match: 0.5,
+                fuzzy_match: 0.6,
+                core_command: 0.67,
+                niche_variant: 1.33,
+                lexical_match: search::LEXICAL_BOOST,
+                installed: 1.0,
+            },
+            intent: IntentThresholds {
+                complex_min_terms: search::INTENT_COMPLEX_MIN_TERMS,
+                min_score_simple: search::INTENT_MIN_SCORE_SIMPLE,
+                min_score_complex: search::INTENT_MIN_SCORE_COMPLEX,
+            },
+            rerank: RerankSettings {
+                enabled: false,
+                top_k: 5,
+            },
+        }
+    }
+}
+
+/// On-disk shape of `ranking.toml`. Every field is optional so a file only overrides what it names.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    name: Option<String>,
+    /// Replaces the core command list entirely.
+    core_commands: Option<Vec<String>>,
+    /// Appended after `core_commands` (e.g. `["podman", "nerdctl"]`).
+    extra_core_commands: Option<Vec<String>>,
+    /// Removed after `core_commands` and `extra_core_commands` are applied (e.g. `["docker"]`).
+    remove_core_commands: Option<Vec<String>>,
+    official_sites: Option<Vec<String>>,
+    max_distance: Option<f64>,
+    weights: Option<WeightsFile>,
+    intent: Option<IntentFile>,
+    rerank: Option<RerankFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WeightsFile {
+    official_site: Option<f64>,
+    exact_match: Option<f64>,
+    fuzzy_match: Option<f64>,
+    core_command: Option<f64>,
+    niche_variant: Option<f64>,
+    lexical_match: Option<f64>,
+    installed: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IntentFile {
+    complex_min_terms: Option<usize>,
+    min_score_simple: Option<f64>,
+    min_score_complex: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RerankFile {
+    enabled: Option<bool>,
+    top_k: Option<usize>,
+}
+
+impl RankingProfile {
+    /// Overlays a parsed profile file on top of the current values.
+    fn apply(&mut self, file: ProfileFile, source: &Path) {
+        if let Some(name) = file.name {
+            self.name = name;
+        } else if self.sources.is_empty() {
+            self.name = "custom".to_string();
+        }
+        self.sources.push(source.to_path_buf());
+
+        if let Some(core) = file.core_commands {
+            self.core_commands = core;
+        }
+        for cmd in file.extra_core_commands.unwrap_or_default() {
+            if !self.core_commands.contains(&cmd) {
+                self.core_commands.push(cmd);
+            }
+        }
+        if let Some(remove) = file.remove_core_commands {
+            self.core_commands.retain(|c| !remove.contains(c));
+        }
+        if let Some(sites) = file.official_sites {
+            self.official_sites = sites;
+        }
+        if let Some(max_distance) = file.max_distance {
+            self.max_distance = max_distance;
+        }
+
+        let weights = file.weights.unwrap_or_default();
+        let w = &mut self.weights;
+        w.official_site = weights.official_site.unwrap_or(w.official_site);
+        w.exact_match = weights.exact_match.unwrap_or(w.exact_match);
+        w.fuzzy_match = weights.fuzzy_match.unwrap_or(w.fuzzy_match);
+        w.core_command = weights.core_command.unwrap_or(w.core_command);
+        w.niche_variant = weights.niche_variant.unwrap_or(w.niche_variant);
+        w.lexical_match = weights.lexical_match.unwrap_or(w.lexical_match);
+        w.installed = weights.installed.unwrap_or(w.installed);
+
+        let intent = file.intent.unwrap_or_default();
+        let t = &mut self.intent;
+        t.complex_min_terms = intent.complex_min_terms.unwrap_or(t.complex_min_terms);
+        t.min_score_simple = intent.min_score_simple.unwrap_or(t.min_score_simple);
+        t.min_score_complex = intent.min_score_complex.unwrap_or(t.min_score_complex);
+
+        let rerank = file.rerank.unwrap_or_default();
+        let r = &mut self.rerank;
+        r.enabled = rerank.enabled.unwrap_or(r.enabled);
+        r.top_k = rerank.top_k.unwrap_or(r.top_k);
+    }
+
+    /// Applies a single profile file; missing files are skipped, malformed ones are an error.
+    pub fn apply_file(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read ranking profile {}", path.display()))?;
+        let file: ProfileFile = toml::from_str(&content)
+            .with_context(|| format!("invalid ranking profile {}", path.display()))?;
+        self.apply(file, path);
+        Ok(())
+    }
+
+    pub fn is_core_command(&self, cmd: &str) -> bool {
+        self.core_commands.iter().any(|c| c == cmd)
+    }
+}
+
+/// System-wide profile shared by every user on the machine (e.g. baked into CI images).
+pub fn global_profile_path() -> Option<PathBuf> {
+    if cfg!(unix) {
+        Some(PathBuf::from("/etc/askman").join(PROFILE_FILE))
+    } else {
+        None
+    }
+}
+
+/// Per-user profile: ~/.config/askman/ranking.toml (linux) or ~/Library/Application Support/askman (mac)
+pub fn user_profile_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("askman").join(PROFILE_FILE))
+}
+
+/// Built-in defaults, then the global profile, then the per-user profile.
+pub fn load_profile() -> Result<RankingProfile> {
+    let mut profile = RankingProfile::default();
+    for path in [global_profile_path(), user_profile_path()]
+        .into_iter()
+        .flatten()
+    {
+        profile.apply_file(&path)?;
+    }
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml_src: &str) -> ProfileFile {
+        toml::from_str(toml_src).unwrap()
+    }
+
+    #[test]
+    fn default_profile_matches_builtin_constants() {
+        let profile = RankingProfile::default();
+        assert_eq!(profile.name, "builtin");
+        assert!(profile.sources.is_empty());
+        assert!(profile.is_core_command("docker"));
+        assert_eq!(profile.max_distance, search::MAX_DISTANCE);
+    }
+
+    #[test]
+    fn file_overrides_only_named_fields() {
+        let mut profile = RankingProfile::default();
+        profile.apply(
+            parse(
+                r#"
+                name = "containers"
+                extra_core_commands = ["podman", "nerdctl"]
+                remove_core_commands = ["docker"]
+
+                [weights]
+                core_command = 0.5
+                "#,
+            ),
+            Path::new("/etc/askman/ranking.toml"),
+        );
+
+        assert_eq!(profile.name, "containers");
+        assert!(profile.is_core_command("podman"));
+        assert!(profile.is_core_command("nerdctl"));
+        assert!(!profile.is_core_command("docker"));
+        assert!(profile.is_core_command("tar"));
+        assert_eq!(profile.weights.core_command, 0.5);
+        assert_eq!(profile.weights.exact_match, 0.5);
+        assert_eq!(profile.intent.complex_min_terms, 3);
+    }
+
+    #[test]
+    fn later_files_layer_over_earlier_ones() {
+        let mut profile = RankingProfile::default();
+        profile.apply(
+            parse("core_commands = [\"tar\"]\nmax_distance = 1.0"),
+            Path::new("global.toml"),
+        );
+        profile.apply(
+            parse("extra_core_commands = [\"podman\"]\n[intent]\nmin_score_complex = 0.75"),
+            Path::new("user.toml"),
+        );
+
+        assert_eq!(profile.name, "custom");
+        assert_eq!(profile.core_commands, vec!["tar", "podman"]);
+        assert_eq!(profile.max_distance, 1.0);
+        assert_eq!(profile.intent.min_score_complex, 0.75);
+        assert!(!profile.rerank.enabled);
+        assert_eq!(
+            profile.sources,
+            vec![PathBuf::from("global.toml"), PathBuf::from("user.toml")]
+        );
+    }
+
+    #[test]
+    fn rerank_can_be_enabled_from_file() {
+        let mut profile = RankingProfile::default();
+        profile.apply(
+            parse("[rerank]\nenabled = true\ntop_k = 3"),
+            Path::new("user.toml"),
+        );
+        assert!(profile.rerank.enabled);
+        assert_eq!(profile.rerank.top_k, 3);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<ProfileFile>("core_comands = [\"tar\"]").is_err());
+    }
+
+    #[test]
+    fn missing_file_is_ignored() {
+        let mut profile = RankingProfile::default();
+        profile
+            .apply_file(Path::new("/nonexistent/askman/ranking.toml"))
+            .unwrap();
+        assert_eq!(profile, RankingProfile::default());
+    }
+}
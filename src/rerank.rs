@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+use std::path::{Path, PathBuf};
+
+use crate::search::{CmdData, Example};
+
+const RERANKER_MODEL: RerankerModel = RerankerModel::BGERerankerBase;
+
+/// Where hf-hub stores the reranker inside askman's model cache.
+pub fn reranker_cache_path(app_dir: &Path) -> PathBuf {
+    let model_code = TextRerank::get_model_info(&RERANKER_MODEL).model_code;
+    app_dir
+        .join("models")
+        .join(format!("models--{}", model_code.replace('/', "--")))
+}
+
+/// True once `askman download-reranker` (or an earlier run) has fetched the model snapshot.
+pub fn is_reranker_cached(app_dir: &Path) -> bool {
+    std::fs::read_dir(reranker_cache_path(app_dir).join("snapshots"))
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false)
+}
+
+/// Loads (and downloads if needed) the cross-encoder reranker.
+pub fn init_reranker(app_dir: &Path, show_download_progress: bool) -> Result<TextRerank> {
+    let cache_dir = app_dir.join("models");
+    let options = RerankInitOptions::new(RERANKER_MODEL)
+        .with_show_download_progress(show_download_progress)
+        .with_cache_dir(cache_dir.clone());
+    TextRerank::try_new(options).with_context(|| {
+        format!(
+            "failed to initialize reranker model BGERerankerBase with cache_dir {}",
+            cache_dir.display()
+        )
+    })
+}
+
+/// Only uses a reranker that is already on disk; queries never trigger the large download.
+pub fn load_cached_reranker(app_dir: &Path) -> Result<Option<TextRerank>> {
+    if !is_reranker_cached(app_dir) {
+        return Ok(None);
+    }
+    init_reranker(app_dir, false).map(Some)
+}
+
+/// Text the cross-encoder scores against the raw query for one (command, example) pair.
+pub fn candidate_text(cmd: &str, data: &CmdData, example: &Example) -> String {
+    format!(
+        "{}: {} {} `{}`",
+        cmd, data.description, example.description, example.syntax
+    )
+}
+
+/// Rescores the top-k commands with the cross-encoder and reorders them by their best example.
+pub fn rerank_results(
+    reranker: &TextRerank,
+    query: &str,
+    sorted: &mut [(String, CmdData)],
+    top_k: usize,
+) -> Result<()> {
+    let mut owners = Vec::new();
+    let mut documents = Vec::new();
+    for (i, (cmd, data)) in sorted.iter().enumerate().take(top_k) {
+        for example in &data.examples {
+            owners.push(i);
+            documents.push(candidate_text(cmd, data, example));
+        }
+    }
+    if documents.is_empty() {
+        return Ok(());
+    }
+
+    let results = reranker.rerank(query.to_string(), documents, false, None)?;
+    let mut scores = vec![None; top_k.min(sorted.len())];
+    for result in results {
+        let slot: &mut Option<f32> = &mut scores[owners[result.index]];
+        if slot.is_none_or(|best| result.score > best) {
+            *slot = Some(result.score);
+        }
+    }
+
+    apply_rerank_scores(sorted, &scores);
+    Ok(())
+}
+
+/// Stores each command's cross-encoder score and reorders the scored prefix, best first.
+/// Commands beyond the prefix keep their original position.
+pub fn apply_rerank_scores(sorted: &mut [(String, CmdData)], scores: &[Option<f32>]) {
+    let k = scores.len().min(sorted.len());
+    for ((_, data), score) in sorted.iter_mut().zip(scores) {
+        data.rerank_score = *score;
+    }
+
+    sorted[..k].sort_by(|a, b| {
+        let a = a.1.rerank_score.unwrap_or(f32::NEG_INFINITY);
+        let b = b.1.rerank_score.unwrap_or(f32::NEG_INFINITY);
+        b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    for (i, (_, data)) in sorted[..k].iter_mut().enumerate() {
+        if let Some(score) = data.rerank_score {
+            data.heuristics
+                .push(format!("rerank #{} (score {:.3})", i + 1, score));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(cmd: &str, score: f64) -> (String, CmdData) {
+        (
+            cmd.to_string(),
+            CmdData::fixture(
+                "desc",
+                vec![Example::new("Example", format!("{cmd} --flag"))],
+                score,
+            ),
+        )
+    }
+
+    #[test]
+    fn reorders_only_the_scored_prefix() {
+        let mut sorted = vec![fixture("a", 0.1), fixture("b", 0.2), fixture("c", 0.3)];
+        apply_rerank_scores(&mut sorted, &[Some(-1.0), Some(2.5)]);
+
+        let order: Vec<&str> = sorted.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(order, vec!["b", "a", "c"]);
+        assert_eq!(sorted[0].1.rerank_score, Some(2.5));
+        assert_eq!(sorted[0].1.heuristics, vec!["rerank #1 (score 2.500)"]);
+        assert_eq!(sorted[2].1.rerank_score, None);
+    }
+
+    #[test]
+    fn cache_path_follows_hf_hub_layout() {
+        let path = reranker_cache_path(Path::new("/data/askman"));
+        assert_eq!(
+            path,
+            PathBuf::from("/data/askman/models/models--BAAI--bge-reranker-base")
+        );
+        assert!(!is_reranker_cached(Path::new("/nonexistent/askman")));
+    }
+}
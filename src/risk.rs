@@ -0,0 +1,396 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::format::{CommandToken, tokenize_command};
+use crate::search::CmdData;
+
+/// Blast radius of running an example, lowest first.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl RiskLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+            RiskLevel::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Risk {
+    pub level: RiskLevel,
+    /// Every rule that fired, in the order they were checked.
+    pub reasons: Vec<String>,
+}
+
+const SHELLS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "perl", "ruby",
+];
+const DOWNLOADERS: &[&str] = &["curl", "wget", "fetch"];
+const FORMATTERS: &[&str] = &["mkfs", "mke2fs", "mkswap", "wipefs"];
+/// Device nodes that are safe to write to.
+const HARMLESS_DEVICES: &[&str] = &[
+    "/dev/null",
+    "/dev/zero",
+    "/dev/stdout",
+    "/dev/stderr",
+    "/dev/stdin",
+    "/dev/tty",
+    "/dev/random",
+    "/dev/urandom",
+];
+const OPERATORS: &[&str] = &["|", "||", "&&", ";", "|&"];
+
+/// Rates an example by pattern rules over its syntax. Placeholders count as written, so
+/// `dd of={{/dev/sdX}}` is a raw device write and `{{[-r|--recursive]}}` is a recursive flag.
+pub fn assess(syntax: &str) -> Risk {
+    let mut risk = Risk {
+        level: RiskLevel::Low,
+        reasons: vec![],
+    };
+    let mut flag = |level: RiskLevel, reason: &str| {
+        risk.level = risk.level.max(level);
+        if !risk.reasons.iter().any(|r| r == reason) {
+            risk.reasons.push(reason.to_string());
+        }
+    };
+
+    let segments = segments(syntax);
+    for (i, (operator, words)) in segments.iter().enumerate() {
+        let (elevated, words) = strip_prefixes(words);
+        if elevated {
+            flag(RiskLevel::Medium, "requires sudo");
+        }
+        let Some(first) = words.first() else {
+            continue;
+        };
+        let command = first.rsplit('/').next().unwrap_or(first);
+        let args = &words[1..];
+
+        if SHELLS.contains(&command)
+            && operator.as_deref() == Some("|")
+            && i > 0
+            && strip_prefixes(&segments[i - 1].1)
+                .1
+                .first()
+                .is_some_and(|c| DOWNLOADERS.contains(&c.as_str()))
+        {
+            flag(RiskLevel::Critical, "pipes a download into a shell");
+        }
+        if args
+            .iter()
+            .any(|a| DOWNLOADERS.iter().any(|d| a.contains(&format!("$({d}"))))
+        {
+            flag(RiskLevel::Critical, "runs a downloaded script");
+        }
+
+        if FORMATTERS.contains(&command) || command.starts_with("mkfs.") {
+            flag(RiskLevel::Critical, "formats or wipes a device");
+        }
+        let device_write = match command {
+            "dd" => args
+                .iter()
+                .any(|a| a.strip_prefix("of=").is_some_and(is_raw_device)),
+            _ => false,
+        } || args
+            .windows(2)
+            .any(|w| (w[0] == ">" || w[0] == ">>") && is_raw_device(&w[1]));
+        if device_write {
+            flag(RiskLevel::Critical, "raw device write");
+        }
+
+        let recursive = has_flag(args, 'r', "--recursive") || has_flag(args, 'R', "--recursive");
+        let force = args.iter().any(|a| a == "--force");
+        match command {
+            "rm" => {
+                if recursive {
+                    flag(RiskLevel::High, "recursive delete");
+                    if args
+                        .iter()
+                        .any(|a| matches!(a.as_str(), "/" | "/*" | "~" | "~/" | "$HOME"))
+                    {
+                        flag(
+                            RiskLevel::Critical,
+                            "recursive delete of a root or home directory",
+                        );
+                    }
+                }
+                if force || has_flag(args, 'f', "--force") {
+                    flag(RiskLevel::Medium, "force flag");
+                }
+            }
+            "mv" | "cp" | "ln" if force || has_flag(args, 'f', "--force") => {
+                flag(RiskLevel::Medium, "force flag");
+            }
+            "chmod" => {
+                if args
+                    .iter()
+                    .any(|a| matches!(a.as_str(), "777" | "0777" | "a+rwx" | "ugo+rwx"))
+                {
+                    flag(RiskLevel::High, "world-writable permissions");
+                }
+                if has_flag(args, 'R', "--recursive") {
+                    flag(RiskLevel::Medium, "recursive permission change");
+                }
+            }
+            "chown" | "chgrp" if has_flag(args, 'R', "--recursive") => {
+                flag(RiskLevel::Medium, "recursive ownership change");
+            }
+            "git" => match args.first().map(String::as_str) {
+                Some("push")
+                    if force
+                        || has_flag(args, 'f', "--force")
+                        || args
+                            .iter()
+                            .any(|a| a.starts_with("--force-with-lease") || a.starts_with('+')) =>
+                {
+                    flag(RiskLevel::High, "force push rewrites remote history");
+                }
+                Some("reset") if args.iter().any(|a| a == "--hard") => {
+                    flag(RiskLevel::High, "discards uncommitted changes");
+                }
+                Some("clean") if force || has_flag(args, 'f', "--force") => {
+                    flag(RiskLevel::High, "deletes untracked files");
+                }
+                _ => {}
+            },
+            "curl"
+                if args.windows(2).any(|w| {
+                    matches!(
+                        w[0].as_str(),
+                        "-d" | "--data" | "--data-binary" | "-F" | "--form"
+                    ) && w[1].contains('@')
+                }) || has_flag(args, 'T', "--upload-file") =>
+            {
+                flag(RiskLevel::Medium, "uploads a local file");
+            }
+            _ => {
+                if force {
+                    flag(RiskLevel::Medium, "force flag");
+                }
+            }
+        }
+    }
+
+    risk
+}
+
+/// Drops examples riskier than `max`; commands left without any example are dropped too,
+/// so the next safe command moves up.
+pub fn hide_above(results: &mut Vec<(String, CmdData)>, max: RiskLevel) {
+    for (_, data) in results.iter_mut() {
+        data.examples.retain(|e| assess(&e.syntax).level <= max);
+    }
+    results.retain(|(_, data)| !data.examples.is_empty());
+}
+
+/// Shell words of each command in the example, with the operator that precedes it.
+/// Adjacent pieces are joined (`of=` + `{{/dev/sdX}}`); flag alternates become separate words.
+fn segments(syntax: &str) -> Vec<(Option<String>, Vec<String>)> {
+    let mut segments = vec![(None, Vec::new())];
+    let mut current = String::new();
+
+    let flush = |current: &mut String, segments: &mut Vec<(Option<String>, Vec<String>)>| {
+        if current.is_empty() {
+            return;
+        }
+        let word = std::mem::take(current);
+        let word = word.trim_matches(|c| c == '"' || c == '\'').to_string();
+        if OPERATORS.contains(&word.as_str()) {
+            segments.push((Some(word), Vec::new()));
+        } else if let Some((_, words)) = segments.last_mut() {
+            words.push(word);
+        }
+    };
+
+    for token in tokenize_command(syntax) {
+        match token {
+            CommandToken::Word(w) => current.push_str(&w),
+            CommandToken::Placeholder { inner, .. } => match flag_alternates(&inner) {
+                Some(alternates) => {
+                    flush(&mut current, &mut segments);
+                    for alternate in alternates {
+                        current.push_str(alternate);
+                        flush(&mut current, &mut segments);
+                    }
+                }
+                None => current.push_str(&inner),
+            },
+            CommandToken::Whitespace(_) => flush(&mut current, &mut segments),
+        }
+    }
+    flush(&mut current, &mut segments);
+    segments
+}
+
+fn flag_alternates(inner: &str) -> Option<Vec<&str>> {
+    let body = inner.strip_prefix('[')?.strip_suffix(']')?;
+    let alternates: Vec<&str> = body.split('|').map(str::trim).collect();
+    alternates
+        .iter()
+        .all(|a| a.starts_with('-'))
+        .then_some(alternates)
+}
+
+/// Skips `sudo`/`doas` and leading `VAR=value` assignments; reports whether privileges were raised.
+fn strip_prefixes(words: &[String]) -> (bool, &[String]) {
+    let mut elevated = false;
+    let mut rest = words;
+    while let Some(first) = rest.first() {
+        if first == "sudo" || first == "doas" {
+            elevated = true;
+        } else if !first.starts_with('-')
+            && first.split_once('=').is_some_and(|(name, _)| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+        {
+            // Environment assignment.
+        } else {
+            break;
+        }
+        rest = &rest[1..];
+    }
+    (elevated, rest)
+}
+
+/// `-r`, a bundle containing it (`-rf`), or the long spelling.
+fn has_flag(args: &[String], short: char, long: &str) -> bool {
+    args.iter().any(|a| {
+        a == long
+            || (a.starts_with('-')
+                && !a.starts_with("--")
+                && a[1..].chars().all(|c| c.is_ascii_alphabetic())
+                && a[1..].contains(short))
+    })
+}
+
+fn is_raw_device(path: &str) -> bool {
+    path.starts_with("/dev/") && !HARMLESS_DEVICES.contains(&path) && !path.starts_with("/dev/fd/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Example;
+
+    fn level(syntax: &str) -> RiskLevel {
+        assess(syntax).level
+    }
+
+    #[test]
+    fn plain_commands_are_low_risk() {
+        assert_eq!(
+            level("ls {{[-l|--long]}} {{path/to/directory}}"),
+            RiskLevel::Low
+        );
+        assert_eq!(
+            level("tar {{[-x|--extract]}} {{[-f|--file]}} {{path/to/source.tar}}"),
+            RiskLevel::Low
+        );
+        assert_eq!(level("dd if={{path/to/file}} of=/dev/null"), RiskLevel::Low);
+        assert_eq!(
+            level("curl {{https://example.com}} | grep {{pattern}}"),
+            RiskLevel::Low
+        );
+    }
+
+    #[test]
+    fn destructive_patterns_are_flagged() {
+        let risk = assess("rm {{[-r|--recursive]}} {{[-f|--force]}} {{path/to/directory}}");
+        assert_eq!(risk.level, RiskLevel::High);
+        assert_eq!(risk.reasons, vec!["recursive delete", "force flag"]);
+        assert_eq!(level("rm -rf /"), RiskLevel::Critical);
+
+        assert_eq!(
+            assess("dd if={{path/to/file.iso}} of={{/dev/usb_drive}} status=progress").reasons,
+            vec!["raw device write"]
+        );
+        assert_eq!(level("cat {{image.img}} > /dev/sdb"), RiskLevel::Critical);
+        assert_eq!(level("mkfs.ext4 {{/dev/sdXY}}"), RiskLevel::Critical);
+
+        let risk = assess("chmod -R 777 {{path/to/directory}}");
+        assert_eq!(risk.level, RiskLevel::High);
+        assert_eq!(
+            risk.reasons,
+            vec!["world-writable permissions", "recursive permission change"]
+        );
+
+        assert_eq!(
+            level("git push {{[-f|--force]}} {{remote}} {{branch}}"),
+            RiskLevel::High
+        );
+        assert_eq!(level("git push {{remote}} {{branch}}"), RiskLevel::Low);
+        assert_eq!(level("git reset --hard {{commit}}"), RiskLevel::High);
+    }
+
+    #[test]
+    fn sudo_and_remote_scripts_are_flagged() {
+        let risk = assess("sudo apt install {{package}}");
+        assert_eq!(risk.level, RiskLevel::Medium);
+        assert_eq!(risk.reasons, vec!["requires sudo"]);
+
+        let risk = assess(
+            "curl {{[-fsSL|--fail --silent --show-error --location]}} {{https://example.com/install.sh}} | sudo bash",
+        );
+        assert_eq!(risk.level, RiskLevel::Critical);
+        assert!(
+            risk.reasons
+                .contains(&"pipes a download into a shell".to_string())
+        );
+        assert_eq!(
+            level("sh -c \"$(curl -fsSL {{url}})\""),
+            RiskLevel::Critical
+        );
+        assert_eq!(
+            level("curl -F file=@{{path/to/file}} {{url}}"),
+            RiskLevel::Medium
+        );
+    }
+
+    #[test]
+    fn hide_above_drops_risky_examples_and_empty_commands() {
+        let data = |examples: Vec<Example>| CmdData::fixture("", examples, 0.5);
+        let mut results = vec![
+            (
+                "dd".to_string(),
+                data(vec![Example::new(
+                    "Write image",
+                    "dd if={{file}} of=/dev/sdb",
+                )]),
+            ),
+            (
+                "rm".to_string(),
+                data(vec![
+                    Example::new("Delete recursively", "rm -r {{path}}"),
+                    Example::new("Delete a file", "rm {{path}}"),
+                ]),
+            ),
+        ];
+        hide_above(&mut results, RiskLevel::Medium);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "rm");
+        assert_eq!(results[0].1.examples.len(), 1);
+        assert_eq!(results[0].1.examples[0].syntax, "rm {{path}}");
+    }
+}
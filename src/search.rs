@@ -1,35 +1,177 @@
 use rusqlite::Connection;
 use rusqlite::params;
+use rust_stemmers::{Algorithm, Stemmer};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::hash_map::Entry;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 use zerocopy::IntoBytes;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::db;
+use crate::fuzzy::{self, TermCorrection};
+use crate::profile::RankingProfile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum TargetOs {
     Linux,
     Osx,
     Windows,
+    FreeBsd,
+    OpenBsd,
+    NetBsd,
 }
 
 impl TargetOs {
+    /// Matches the tldr-pages platform directory name stored in the `os` column.
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Linux => "linux",
             Self::Osx => "osx",
             Self::Windows => "windows",
+            Self::FreeBsd => "freebsd",
+            Self::OpenBsd => "openbsd",
+            Self::NetBsd => "netbsd",
+        }
+    }
+
+    /// Parses a platform name as written in tldr (`osx`) or by users (`macos`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "linux" => Some(Self::Linux),
+            "osx" | "macos" => Some(Self::Osx),
+            "windows" => Some(Self::Windows),
+            "freebsd" => Some(Self::FreeBsd),
+            "openbsd" => Some(Self::OpenBsd),
+            "netbsd" => Some(Self::NetBsd),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug)]
+/// Whether the target OS came from a CLI flag or from the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OsSource {
+    Detected,
+    Flag,
+}
+
+impl OsSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Detected => "detected",
+            Self::Flag => "flag",
+        }
+    }
+}
+
+/// One tldr example of a command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Example {
+    pub description: String,
+    pub syntax: String,
+    /// Distance of this example's embedding to the query; `None` until scored.
+    pub distance: Option<f64>,
+}
+
+impl Example {
+    pub fn new(description: impl Into<String>, syntax: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            syntax: syntax.into(),
+            distance: None,
+        }
+    }
+
+    fn scored(description: String, syntax: String, distance: f64) -> Self {
+        Self {
+            description,
+            syntax,
+            distance: Some(distance),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CmdData {
     pub description: String,
     pub platform: String,
-    pub examples: Vec<(String, String)>,
+    /// Most relevant first once `sort_examples` has run.
+    pub examples: Vec<Example>,
     pub adjusted_score: f64,
     pub raw_distance: f64,
     pub heuristics: Vec<String>,
+    /// Whether the base command resolves on `$PATH` (set by `annotate_installed`).
+    pub installed: bool,
+    /// Cross-encoder score of the best example when the reranker ran (higher is better).
+    pub rerank_score: Option<f32>,
+    /// Set when this page documents a subcommand of another tool (`git-stash`).
+    pub family: Option<CommandFamily>,
+}
+
+#[cfg(test)]
+impl CmdData {
+    /// A `common` page scored `score`, not installed, reranked or part of a family.
+    pub(crate) fn fixture(description: &str, examples: Vec<Example>, score: f64) -> Self {
+        Self {
+            description: description.to_string(),
+            platform: "common".to_string(),
+            examples,
+            adjusted_score: score,
+            raw_distance: score,
+            heuristics: vec![],
+            installed: false,
+            rerank_score: None,
+            family: None,
+        }
+    }
+}
+
+/// Parent tool and subcommand of a `tool-sub` page, as recorded by the importer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandFamily {
+    /// The tool's own page name (`git`).
+    pub family: String,
+    /// As typed after the tool (`stash`, `s3 cp`).
+    pub subcommand: String,
+}
+
+impl CommandFamily {
+    /// Whether the query spells out `tool subcommand` as consecutive words ("git stash pop").
+    pub fn is_named_in(&self, query: &str) -> bool {
+        let wanted: Vec<&str> = std::iter::once(self.family.as_str())
+            .chain(self.subcommand.split_whitespace())
+            .collect();
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|w| {
+                w.trim_matches(|c: char| !c.is_alphanumeric() && c != '-')
+                    .to_lowercase()
+            })
+            .collect();
+        words
+            .windows(wanted.len())
+            .any(|window| window.iter().zip(&wanted).all(|(a, b)| a == b))
+    }
+}
+
+/// Subcommand pages keyed by page name (`git-stash` -> git / stash).
+pub type CommandFamilies = HashMap<String, CommandFamily>;
+
+/// What is known about the query before ranking, beyond its text and embedding.
+#[derive(Debug)]
+pub struct QueryContext<'a> {
+    /// Misspelled command names and what they were corrected to.
+    pub corrections: Vec<TermCorrection>,
+    pub families: &'a CommandFamilies,
+    /// Restricts ranking to these commands (`--tool`); `None` searches everything.
+    pub scope: Option<&'a ToolScope>,
+    /// Distinct commands the caller needs; the KNN widens until it finds them or hits `KNN_MAX_K`.
+    pub min_commands: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -37,13 +179,17 @@ pub struct IntentCoverage {
     pub score: f64,
     pub matched_terms: Vec<String>,
     pub missing_terms: Vec<String>,
+    /// Negated query words ("without compression", "skip hidden") the result must not contradict.
+    pub constraints: Vec<String>,
+    /// Constraints the best example asserts anyway; any violation makes the coverage weak.
+    pub violated_constraints: Vec<String>,
     pub strong: bool,
 }
 
 pub type CmdMap = HashMap<String, CmdData>;
 
 /// Domains that indicate an "official" man page source
-const OFFICIAL_SITES: &[&str] = &[
+pub const OFFICIAL_SITES: &[&str] = &[
     "gnu.",
     "kernel.",
     "man7.",
@@ -54,7 +200,8 @@ const OFFICIAL_SITES: &[&str] = &[
     "greenwoodsoftware.",
 ];
 
-/// Whitelist of core canonical UNIX utilities that should always be prioritized
+/// Whitelist of core canonical UNIX utilities that should always be prioritized.
+/// Built-in default; `ranking.toml` can replace or extend it (see `profile`).
 pub const CORE_COMMANDS: &[&str] = &[
     "tar",
     "grep",
@@ -154,34 +301,111 @@ pub const CORE_COMMANDS: &[&str] = &[
     "screen",
 ];
 
-/// Cosine distance threshold (0 = identical, 2 = opposite): filters out unrelated matches.
-/// sqlite-vec's vec0 table returns cosine distance by default via the `distance` column.
-/// See: https://alexgarcia.xyz/sqlite-vec/api-reference.html#vec_distance_cosine
+/// L2 distance threshold: filters out unrelated matches. The embeddings are unit length, so
+/// distances run from 0 (identical) to 2 (opposite).
+/// sqlite-vec's vec0 table reports L2 distance in its `distance` column unless the column
+/// declares another `distance_metric`.
+/// See: https://alexgarcia.xyz/sqlite-vec/api-reference.html#vec_distance_l2
 pub const MAX_DISTANCE: f64 = 1.10;
 pub const HYDRATE_MIN_EXAMPLES: usize = 3;
 pub const HYDRATE_MAX_EXAMPLES: usize = 12;
 pub const INTENT_COMPLEX_MIN_TERMS: usize = 3;
 pub const INTENT_MIN_SCORE_SIMPLE: f64 = 0.50;
 pub const INTENT_MIN_SCORE_COMPLEX: f64 = 0.60;
+/// Minimum intent terms each side of a bare "and" needs before it is treated as two steps.
+pub const DECOMPOSE_MIN_STEP_TERMS: usize = 2;
+/// Reciprocal rank fusion damping constant (the usual RRF default).
+pub const RRF_K: f64 = 60.0;
+/// Default distance multiplier for rows both rankings place equally high.
+pub const LEXICAL_BOOST: f64 = 0.85;
+/// KNN rows (examples, not commands) fetched on the first pass.
+pub const KNN_BASE_K: usize = 23;
+/// The KNN doubles up to this many rows while too few distinct commands come back.
+pub const KNN_MAX_K: usize = 368;
+
+/// Words that turn the next content word into an exclusion ("tar without compression").
+const NEGATION_CUES: &[&str] = &[
+    "without",
+    "no",
+    "not",
+    "never",
+    "except",
+    "excluding",
+    "exclude",
+    "skip",
+    "skipping",
+    "ignore",
+    "ignoring",
+    "omit",
+    "omitting",
+    "avoid",
+    "avoiding",
+    "don't",
+    "dont",
+    "minus",
+];
+/// Filler between a cue and the word it negates ("without any compression").
+const NEGATION_FILLERS: &[&str] = &["any", "all", "some", "those", "these"];
+/// Conjunctions that close a negated phrase and carry no intent themselves.
+const CLAUSE_BREAKS: &[&str] = &["but", "and", "or", "then", "while", "yet", "so"];
 
 const INTENT_STOPWORDS: &[&str] = &[
     "a", "an", "and", "as", "at", "by", "for", "from", "get", "how", "in", "into", "list", "of",
     "on", "or", "run", "show", "the", "then", "to", "using", "with",
 ];
 
-pub fn get_target_os(linux: bool, osx: bool, windows: bool) -> TargetOs {
+pub fn get_target_os(linux: bool, osx: bool, windows: bool) -> (TargetOs, OsSource) {
     match (linux, osx, windows) {
-        (true, _, _) => TargetOs::Linux,
-        (_, true, _) => TargetOs::Osx,
-        (_, _, true) => TargetOs::Windows,
-        _ => match std::env::consts::OS {
-            "macos" => TargetOs::Osx,
-            "windows" => TargetOs::Windows,
-            _ => TargetOs::Linux,
+        (true, _, _) => (TargetOs::Linux, OsSource::Flag),
+        (_, true, _) => (TargetOs::Osx, OsSource::Flag),
+        (_, _, true) => (TargetOs::Windows, OsSource::Flag),
+        _ => (detect_host_os(std::env::consts::OS), OsSource::Detected),
+    }
+}
+
+/// Resolves an optional platform name (`"osx"`, `"macos"`, ...) from a JSON request; absent
+/// means the host OS, as with no `--linux`/`--osx`/`--windows` flag.
+pub fn resolve_target_os(os: Option<&str>) -> anyhow::Result<(TargetOs, OsSource)> {
+    match os {
+        None => Ok(get_target_os(false, false, false)),
+        Some(name) => match TargetOs::from_name(name) {
+            Some(target_os) => Ok((target_os, OsSource::Flag)),
+            None => anyhow::bail!("unknown os `{name}`"),
         },
     }
 }
 
+/// Maps a `std::env::consts::OS` value to the tldr platform it should search.
+/// Other Unix-likes (illumos, solaris, android, ...) fall back to Linux pages as the closest match.
+pub fn detect_host_os(host: &str) -> TargetOs {
+    match host {
+        "macos" | "ios" => TargetOs::Osx,
+        "windows" => TargetOs::Windows,
+        // DragonFly BSD is a FreeBSD fork and shares most of its userland.
+        "freebsd" | "dragonfly" => TargetOs::FreeBsd,
+        "openbsd" => TargetOs::OpenBsd,
+        "netbsd" => TargetOs::NetBsd,
+        _ => TargetOs::Linux,
+    }
+}
+
+/// The platform whose pages a search on `target_os` reads. tldr has few or no BSD pages yet, so a
+/// BSD target without rows in the database reads Linux pages instead, as other Unix-likes do.
+pub fn page_os(conn: &Connection, target_os: TargetOs) -> anyhow::Result<TargetOs> {
+    if !matches!(
+        target_os,
+        TargetOs::FreeBsd | TargetOs::OpenBsd | TargetOs::NetBsd
+    ) {
+        return Ok(target_os);
+    }
+    let mut stmt = conn.prepare("SELECT 1 FROM pages_vec WHERE os = ?1 LIMIT 1")?;
+    Ok(if stmt.exists(params![target_os.as_str()])? {
+        target_os
+    } else {
+        TargetOs::Linux
+    })
+}
+
 /// Pure scoring function: adjusts raw distance based on command name and description heuristics.
 /// Returns `None` if the result should be filtered out (score above threshold).
 pub fn adjust_score(
@@ -189,16 +413,22 @@ pub fn adjust_score(
     cmd: &str,
     desc: &str,
     raw_distance: f64,
+    profile: &RankingProfile,
+    family: Option<&CommandFamily>,
 ) -> Option<(f64, Vec<String>)> {
-    if raw_distance > MAX_DISTANCE {
+    if raw_distance > profile.max_distance {
         return None;
     }
 
+    let weights = &profile.weights;
     let mut applied_heuristics = Vec::new();
-    let is_official = OFFICIAL_SITES.iter().any(|&site| desc.contains(site));
+    let is_official = profile
+        .official_sites
+        .iter()
+        .any(|site| desc.contains(site.as_str()));
     let mut score = if is_official {
-        applied_heuristics.push("official_site (0.8x)".to_string());
-        raw_distance * 0.8
+        applied_heuristics.push(format!("official_site ({}x)", weights.official_site));
+        raw_distance * weights.official_site
     } else {
         raw_distance
     };
@@ -206,109 +436,253 @@ pub fn adjust_score(
     // Explicit Intent Multiplier: If the user explicitly typed the command in the query
     let query_words: Vec<&str> = query.split_whitespace().collect();
     if query_words.contains(&cmd) {
-        applied_heuristics.push("exact_match (0.5x)".to_string());
-        score *= 0.5; // Massive boost for explicit intent
+        applied_heuristics.push(format!("exact_match ({}x)", weights.exact_match));
+        score *= weights.exact_match; // Massive boost for explicit intent
+    } else if let Some(family) = family
+        && family.is_named_in(query)
+    {
+        // "git stash pop" names git-stash just as explicitly as typing the page name.
+        applied_heuristics.push(format!("subcommand_match ({}x)", weights.exact_match));
+        score *= weights.exact_match;
     }
 
-    if CORE_COMMANDS.contains(&cmd) {
-        applied_heuristics.push("core_command (0.67x)".to_string());
-        score *= 0.67; // Boost canonical core tools
+    // Subcommand pages share their tool's standing (`git-stash` is as core as `git`).
+    let core_name = family.map_or(cmd, |f| f.family.as_str());
+    if profile.is_core_command(core_name) {
+        applied_heuristics.push(format!("core_command ({}x)", weights.core_command));
+        score *= weights.core_command; // Boost canonical core tools
+    } else if family.is_some() {
+        // Subcommand pages of a known tool are first-class, not niche variants.
     } else if cmd.contains('-')
         || cmd.starts_with('q')
         || cmd.starts_with('z')
         || (cmd.ends_with("grep") && cmd != "grep")
         || cmd.ends_with("all")
     {
-        applied_heuristics.push("niche_variant (1.33x)".to_string());
-        score *= 1.33; // Penalize niche variants
+        applied_heuristics.push(format!("niche_variant ({}x)", weights.niche_variant));
+        score *= weights.niche_variant; // Penalize niche variants
     }
 
     Some((score, applied_heuristics))
 }
 
+/// Graded `exact_match`: a perfect correction earns the full `fuzzy_match` weight,
+/// costlier ones move toward 1.0 (no boost).
+pub fn fuzzy_weight(profile: &RankingProfile, correction: &TermCorrection) -> f64 {
+    1.0 - (1.0 - profile.weights.fuzzy_match) * correction.similarity()
+}
+
+/// Every distinct command name in the database, sorted.
+pub fn load_command_names(conn: &Connection) -> anyhow::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT command FROM pages_vec")?;
+    let mut names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    Ok(names)
+}
+
+/// True if `word` occurs anywhere in the indexed pages; such words are not typos.
+/// Databases built before `pages_fts` existed are scanned instead. That is slow, but it only
+/// runs for the few query words that look like a misspelled command name.
+pub fn corpus_contains(conn: &Connection, word: &str) -> anyhow::Result<bool> {
+    if !db::has_table(conn, "pages_fts")? {
+        let pattern = format!(
+            "%{}%",
+            word.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let mut stmt = conn.prepare(
+            "SELECT 1 FROM pages_vec
+             WHERE description LIKE ?1 ESCAPE '\\'
+                OR example_desc LIKE ?1 ESCAPE '\\'
+                OR example_cmd LIKE ?1 ESCAPE '\\'
+             LIMIT 1",
+        )?;
+        return Ok(stmt.exists(params![pattern])?);
+    }
+    let phrase = format!("\"{}\"", word.replace('"', "\"\""));
+    let mut stmt = conn.prepare("SELECT 1 FROM pages_fts WHERE pages_fts MATCH ?1 LIMIT 1")?;
+    Ok(stmt.exists(params![phrase])?)
+}
+
+/// Finds misspelled command names in the query (see `fuzzy::correct_terms`).
+pub fn correct_query_terms(
+    conn: &Connection,
+    query: &str,
+    commands: &[String],
+) -> anyhow::Result<Vec<TermCorrection>> {
+    let mut failure = None;
+    let corrections = fuzzy::correct_terms(query, commands, |word| {
+        corpus_contains(conn, word).unwrap_or_else(|e| {
+            failure.get_or_insert(e);
+            true
+        })
+    });
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(corrections),
+    }
+}
+
+/// One `pages_vec` row considered for ranking, with its raw cosine distance to the query.
+struct Candidate {
+    command: String,
+    os: String,
+    description: String,
+    example_desc: String,
+    example_cmd: String,
+    distance: f64,
+}
+
+/// Position of a candidate row after reciprocal rank fusion (all ranks are 1-based).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusedRank {
+    pub rowid: i64,
+    pub rank: usize,
+    pub vector_rank: Option<usize>,
+    pub lexical_rank: Option<usize>,
+    pub score: f64,
+}
+
 pub fn perform_search(
     conn: &Connection,
     query: &str,
     q_vec: &[f32],
     target_os: TargetOs,
     cross_platform: bool,
+    profile: &RankingProfile,
+    context: &QueryContext,
 ) -> anyhow::Result<Vec<(String, CmdData)>> {
     let q_blob = q_vec.as_bytes();
+    let corrections = context.corrections.as_slice();
 
-    let mut results_vec = Vec::new();
+    let mut candidates: HashMap<i64, Candidate> = HashMap::new();
+    let mut vector_ranked = Vec::new();
 
-    if cross_platform {
-        let mut stmt = conn.prepare(
-            "SELECT command, os, description, example_desc, example_cmd, distance
-             FROM pages_vec
-             WHERE embedding MATCH ?1
-             ORDER BY distance
-             LIMIT 23;",
-        )?;
-        let mapped = stmt.query_map(params![q_blob], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, String>(4)?,
-                row.get::<_, f64>(5)?,
-            ))
-        })?;
-        for r in mapped {
-            results_vec.push(r?);
+    // Rows are examples, so one chatty page can fill the first k on its own.
+    let mut k = KNN_BASE_K;
+    let hits = loop {
+        let hits = match context.scope {
+            Some(scope) => scoped_vector_search(conn, q_blob, scope, target_os, cross_platform, k)?,
+            None => vector_search(conn, q_blob, target_os, cross_platform, k)?,
+        };
+        let distinct = hits
+            .iter()
+            .map(|(_, c)| c.command.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+        if distinct >= context.min_commands || hits.len() < k || k >= KNN_MAX_K {
+            break hits;
         }
-    } else {
-        let mut stmt = conn.prepare(
-            "SELECT command, description, example_desc, example_cmd, distance
-             FROM pages_vec
-             WHERE (os = 'common' OR os = ?2) AND embedding MATCH ?1
-             ORDER BY distance
-             LIMIT 23;",
-        )?;
-        let mapped = stmt.query_map(params![q_blob, target_os.as_str()], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                target_os.as_str().to_string(), // os_tag
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, f64>(4)?,
-            ))
-        })?;
-        for r in mapped {
-            results_vec.push(r?);
+        k = (k * 2).min(KNN_MAX_K);
+    };
+    for (rowid, candidate) in hits {
+        vector_ranked.push(rowid);
+        candidates.insert(rowid, candidate);
+    }
+
+    // Exact flags and rare tokens ("rsync --partial", "xargs -0") are where embeddings are weakest.
+    let corrected_query = fuzzy::apply_corrections(query, corrections);
+    // Same depth as the vector side, so neither dominates the fusion by sheer length.
+    let mut lexical_ranked = lexical_search(conn, &corrected_query, target_os, cross_platform, k)?;
+    for &rowid in &lexical_ranked {
+        if let Entry::Vacant(e) = candidates.entry(rowid)
+            && let Some(candidate) =
+                fetch_candidate(conn, rowid, q_blob, target_os, cross_platform)?
+        {
+            e.insert(candidate);
         }
     }
+    if let Some(scope) = context.scope {
+        lexical_ranked.retain(|rowid| {
+            candidates
+                .get(rowid)
+                .is_some_and(|c| scope.contains(&c.command))
+        });
+    }
 
     let mut command_map: CmdMap = HashMap::new();
 
-    for (cmd, os_tag, desc, ex_desc, ex_cmd, raw_distance) in results_vec {
-        let (adjusted_score, heuristics) = match adjust_score(query, &cmd, &desc, raw_distance) {
-            Some(s) => s,
-            None => {
-                continue;
-            }
+    for fused in fuse_rankings(&vector_ranked, &lexical_ranked, RRF_K) {
+        let Some(candidate) = candidates.remove(&fused.rowid) else {
+            continue;
         };
+        let Candidate {
+            command: cmd,
+            os: os_tag,
+            description: desc,
+            example_desc: ex_desc,
+            example_cmd: ex_cmd,
+            distance: raw_distance,
+        } = candidate;
+
+        let mut heuristics = vec![format_fused_rank(&fused)];
+        let mut fused_distance = raw_distance;
+        let weight = fusion_weight(
+            &fused,
+            vector_ranked.len(),
+            RRF_K,
+            profile.weights.lexical_match,
+        );
+        if weight != 1.0 {
+            heuristics.push(format!("lexical_match ({weight:.2}x)"));
+            fused_distance *= weight;
+        }
+
+        let family = context.families.get(&cmd);
+        let (mut adjusted_score, applied) =
+            match adjust_score(query, &cmd, &desc, fused_distance, profile, family) {
+                Some(s) => s,
+                None => {
+                    continue;
+                }
+            };
+        heuristics.extend(applied);
+
+        // A misspelled command name ("rsycn") still signals explicit intent, scaled by how sure the correction is.
+        if let Some(correction) = corrections.iter().find(|c| c.corrected == cmd) {
+            let weight = fuzzy_weight(profile, correction);
+            heuristics.push(format!(
+                "fuzzy_match ({} -> {}, {:.2}x)",
+                correction.original, correction.corrected, weight
+            ));
+            adjusted_score *= weight;
+        }
 
         match command_map.entry(cmd.clone()) {
             Entry::Vacant(e) => {
                 e.insert(CmdData {
                     description: desc,
                     platform: os_tag,
-                    examples: vec![(ex_desc, ex_cmd)],
+                    examples: vec![Example::scored(ex_desc, ex_cmd, raw_distance)],
                     adjusted_score,
                     raw_distance,
                     heuristics,
+                    installed: false,
+                    rerank_score: None,
+                    family: family.cloned(),
                 });
             }
             Entry::Occupied(mut o) => {
-                o.get_mut().examples.push((ex_desc, ex_cmd));
+                let data = o.get_mut();
+                data.examples
+                    .push(Example::scored(ex_desc, ex_cmd, raw_distance));
+                // Fused order is not distance order, so the command keeps its best-scoring row.
+                if adjusted_score < data.adjusted_score {
+                    data.adjusted_score = adjusted_score;
+                    data.raw_distance = raw_distance;
+                    data.heuristics = heuristics;
+                }
             }
         }
     }
 
     let mut sorted: Vec<(String, CmdData)> = command_map.into_iter().collect();
+    for (_, data) in sorted.iter_mut() {
+        sort_examples(&mut data.examples);
+    }
     sorted.sort_by(|a, b| {
         a.1.adjusted_score
             .partial_cmp(&b.1.adjusted_score)
@@ -318,152 +692,895 @@ pub fn perform_search(
     Ok(sorted)
 }
 
-/// Raises example depth for thin top hits so the JSON output meets the policy guard.
-/// After exercises, consider tightening platform filtering or emitting per-example OS annotations.
-pub fn hydrate_top_result_examples(
-    conn: &Connection,
-    sorted: &mut [(String, CmdData)],
-    query: &str,
-    target_os: TargetOs,
-    cross_platform: bool,
-    min_examples: usize,
-    max_examples: usize,
-) -> anyhow::Result<usize> {
-    let Some((command, data)) = sorted.first_mut() else {
-        return Ok(0);
-    };
+/// Orders examples by their own distance to the query; unscored ones keep their order at the end.
+pub fn sort_examples(examples: &mut [Example]) {
+    examples.sort_by(|a, b| match (a.distance, b.distance) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
 
-    if data.examples.len() >= min_examples {
-        return Ok(0);
+/// Gives hydrated (unscored) examples a distance to the query, then re-sorts the command's examples.
+pub fn score_examples(
+    conn: &Connection,
+    command: &str,
+    data: &mut CmdData,
+    q_vec: &[f32],
+) -> anyhow::Result<()> {
+    if data.examples.iter().all(|e| e.distance.is_some()) {
+        return Ok(());
     }
 
-    // Avoid enriching unrelated top hits; only hydrate when query clearly targets this command.
-    if !query_mentions_command_family(query, command) || !is_simple_intent_query(query) {
-        return Ok(0);
+    let mut stmt = conn.prepare(
+        "SELECT vec_distance_l2(embedding, ?1)
+         FROM pages_vec
+         WHERE command = ?2 AND example_desc = ?3 AND example_cmd = ?4
+         LIMIT 1",
+    )?;
+    for example in data.examples.iter_mut().filter(|e| e.distance.is_none()) {
+        let mut rows = stmt.query(params![
+            q_vec.as_bytes(),
+            command,
+            example.description,
+            example.syntax
+        ])?;
+        if let Some(row) = rows.next()? {
+            example.distance = Some(row.get(0)?);
+        }
     }
 
-    hydrate_examples_for_command(conn, command, data, target_os, cross_platform, max_examples)
-}
-
-fn query_mentions_command_family(query: &str, command: &str) -> bool {
-    let family = command.split('-').next().unwrap_or(command);
-    let query_words: Vec<String> = query
-        .split_whitespace()
-        .map(|w| {
-            w.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-')
-                .to_ascii_lowercase()
-        })
-        .filter(|w| !w.is_empty())
-        .collect();
-
-    let command_lc = command.to_ascii_lowercase();
-    let family_lc = family.to_ascii_lowercase();
-
-    query_words
-        .iter()
-        .any(|w| w == &command_lc || w == &family_lc)
+    sort_examples(&mut data.examples);
+    Ok(())
 }
 
-fn is_simple_intent_query(query: &str) -> bool {
-    let q = query.to_ascii_lowercase();
-    // Multi-step intent is better handled by decomposition instead of auto-expanding one command.
-    if q.contains(" and ") || q.contains(" then ") || q.contains(';') || q.contains(" with ") {
-        return false;
+/// The `k` nearest example rows for the target OS (or every OS in cross-platform mode).
+fn vector_search(
+    conn: &Connection,
+    q_blob: &[u8],
+    target_os: TargetOs,
+    cross_platform: bool,
+    k: usize,
+) -> anyhow::Result<Vec<(i64, Candidate)>> {
+    let mut hits = Vec::new();
+    if cross_platform {
+        let mut stmt = conn.prepare(
+            "SELECT rowid, command, os, description, example_desc, example_cmd, distance
+             FROM pages_vec
+             WHERE embedding MATCH ?1
+             ORDER BY distance
+             LIMIT ?2;",
+        )?;
+        let mapped = stmt.query_map(params![q_blob, k as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                Candidate {
+                    command: row.get(1)?,
+                    os: row.get(2)?,
+                    description: row.get(3)?,
+                    example_desc: row.get(4)?,
+                    example_cmd: row.get(5)?,
+                    distance: row.get(6)?,
+                },
+            ))
+        })?;
+        for r in mapped {
+            hits.push(r?);
+        }
+    } else {
+        let mut stmt = conn.prepare(
+            "SELECT rowid, command, description, example_desc, example_cmd, distance
+             FROM pages_vec
+             WHERE (os = 'common' OR os = ?2) AND embedding MATCH ?1
+             ORDER BY distance
+             LIMIT ?3;",
+        )?;
+        let mapped = stmt.query_map(params![q_blob, target_os.as_str(), k as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                Candidate {
+                    command: row.get(1)?,
+                    os: target_os.as_str().to_string(), // os_tag
+                    description: row.get(2)?,
+                    example_desc: row.get(3)?,
+                    example_cmd: row.get(4)?,
+                    distance: row.get(5)?,
+                },
+            ))
+        })?;
+        for r in mapped {
+            hits.push(r?);
+        }
     }
-
-    let words = q.split_whitespace().count();
-    words <= 6
+    Ok(hits)
 }
 
-pub fn evaluate_intent_coverage(query: &str, command: &str, data: &CmdData) -> IntentCoverage {
-    // Lightweight lexical coverage check used as an execution guard for partial semantic matches.
-    // Improvement idea after running the new tests: tune term filtering so short or stopword-heavy queries still give useful warnings.
-    let query_terms = extract_intent_terms(query);
-    if query_terms.is_empty() {
-        return IntentCoverage {
-            score: 0.0,
-            matched_terms: vec![],
-            missing_terms: vec![],
-            strong: false,
-        };
-    }
-
-    let mut corpus = String::new();
-    corpus.push_str(command);
-    corpus.push(' ');
-    corpus.push_str(&data.description);
-    corpus.push(' ');
-    for (desc, ex_cmd) in &data.examples {
-        corpus.push_str(desc);
-        corpus.push(' ');
-        corpus.push_str(ex_cmd);
-        corpus.push(' ');
+/// Ranks rows through the FTS5 index. Returns nothing for databases built before `pages_fts` existed.
+fn lexical_search(
+    conn: &Connection,
+    query: &str,
+    target_os: TargetOs,
+    cross_platform: bool,
+    k: usize,
+) -> anyhow::Result<Vec<i64>> {
+    let Some(fts_query) = build_fts_query(query) else {
+        return Ok(vec![]);
+    };
+    if !db::has_table(conn, "pages_fts")? {
+        return Ok(vec![]);
     }
 
-    let corpus_terms = extract_intent_terms(&corpus);
-    let corpus_set: HashSet<&str> = corpus_terms.iter().map(String::as_str).collect();
-
-    let mut matched_terms = Vec::new();
-    let mut missing_terms = Vec::new();
-    for term in &query_terms {
-        if corpus_set.contains(term.as_str()) {
-            matched_terms.push(term.clone());
-        } else {
-            missing_terms.push(term.clone());
+    // bm25 column weights: command, description, example_desc, example_cmd (os is unindexed).
+    let sql = if cross_platform {
+        "SELECT rowid FROM pages_fts
+         WHERE pages_fts MATCH ?1
+         ORDER BY bm25(pages_fts, 10.0, 1.0, 2.0, 5.0)
+         LIMIT ?2;"
+    } else {
+        "SELECT rowid FROM pages_fts
+         WHERE pages_fts MATCH ?1 AND (os = 'common' OR os = ?2)
+         ORDER BY bm25(pages_fts, 10.0, 1.0, 2.0, 5.0)
+         LIMIT ?3;"
+    };
+    let mut stmt = conn.prepare(sql)?;
+    let mut rowids = Vec::new();
+    if cross_platform {
+        for r in stmt.query_map(params![fts_query, k as i64], |row| row.get::<_, i64>(0))? {
+            rowids.push(r?);
+        }
+    } else {
+        let mapped = stmt.query_map(params![fts_query, target_os.as_str(), k as i64], |row| {
+            row.get::<_, i64>(0)
+        })?;
+        for r in mapped {
+            rowids.push(r?);
         }
     }
+    Ok(rowids)
+}
 
-    let score = matched_terms.len() as f64 / query_terms.len() as f64;
-    // Demand higher coverage for longer queries because they encode more explicit constraints.
-    let min_score = if query_terms.len() >= INTENT_COMPLEX_MIN_TERMS {
-        INTENT_MIN_SCORE_COMPLEX
-    } else {
-        INTENT_MIN_SCORE_SIMPLE
+/// Loads a lexical-only hit and scores it against the query vector so it can share the distance scale.
+/// `vec0` tables without a `distance_metric` rank by L2, so the same metric is used here.
+fn fetch_candidate(
+    conn: &Connection,
+    rowid: i64,
+    q_blob: &[u8],
+    target_os: TargetOs,
+    cross_platform: bool,
+) -> anyhow::Result<Option<Candidate>> {
+    let mut stmt = conn.prepare(
+        "SELECT command, os, description, example_desc, example_cmd,
+                vec_distance_l2(embedding, ?2)
+         FROM pages_vec
+         WHERE rowid = ?1",
+    )?;
+    let mut rows = stmt.query(params![rowid, q_blob])?;
+    let Some(row) = rows.next()? else {
+        return Ok(None);
     };
 
-    IntentCoverage {
-        score,
-        matched_terms,
-        missing_terms,
-        strong: score >= min_score,
-    }
+    let os: String = row.get(1)?;
+    Ok(Some(Candidate {
+        command: row.get(0)?,
+        os: if cross_platform {
+            os
+        } else {
+            target_os.as_str().to_string()
+        },
+        description: row.get(2)?,
+        example_desc: row.get(3)?,
+        example_cmd: row.get(4)?,
+        distance: row.get(5)?,
+    }))
 }
 
-fn extract_intent_terms(text: &str) -> Vec<String> {
+/// Turns a free-text question into an FTS5 `OR` query of quoted terms.
+/// Flags keep their bare name ("--partial" -> "partial") to line up with the unicode61 tokenizer.
+pub fn build_fts_query(query: &str) -> Option<String> {
     let mut terms = Vec::new();
-    let mut seen = HashSet::new();
-
-    for raw in text.split_whitespace() {
-        let token = raw
-            .trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-')
-            .to_ascii_lowercase();
-        if token.is_empty() {
-            continue;
-        }
-        if INTENT_STOPWORDS.contains(&token.as_str()) {
+    for raw in query.split(|c: char| !c.is_alphanumeric()) {
+        let token = raw.to_lowercase();
+        if token.is_empty() || INTENT_STOPWORDS.contains(&token.as_str()) {
             continue;
         }
-        push_intent_term(&token, &mut terms, &mut seen);
-        if token.contains('-') {
-            for part in token.split('-') {
-                push_intent_term(part, &mut terms, &mut seen);
-            }
+        let quoted = format!("\"{token}\"");
+        if !terms.contains(&quoted) {
+            terms.push(quoted);
         }
     }
 
-    terms
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
 }
 
-fn push_intent_term(token: &str, terms: &mut Vec<String>, seen: &mut HashSet<String>) {
-    if token.len() < 2 {
-        return;
-    }
+/// Reciprocal rank fusion: each list contributes `1 / (k + rank)` for every row it contains.
+pub fn fuse_rankings(vector: &[i64], lexical: &[i64], k: f64) -> Vec<FusedRank> {
+    let mut fused: Vec<FusedRank> = Vec::new();
+    let mut index: HashMap<i64, usize> = HashMap::new();
+
+    for (list, is_vector) in [(vector, true), (lexical, false)] {
+        for (i, &rowid) in list.iter().enumerate() {
+            let rank = i + 1;
+            let slot = *index.entry(rowid).or_insert_with(|| {
+                fused.push(FusedRank {
+                    rowid,
+                    rank: 0,
+                    vector_rank: None,
+                    lexical_rank: None,
+                    score: 0.0,
+                });
+                fused.len() - 1
+            });
+            let entry = &mut fused[slot];
+            if is_vector {
+                entry.vector_rank.get_or_insert(rank);
+            } else {
+                entry.lexical_rank.get_or_insert(rank);
+            }
+            entry.score += 1.0 / (k + rank as f64);
+        }
+    }
+
+    // Stable sort keeps vector order as the tie-breaker.
+    fused.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for (i, entry) in fused.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    fused
+}
+
+/// Folds a row's RRF score into its distance. The weight is `lexical_match` raised to twice the
+/// lexical share of the score: 1.0 for vector-only rows, `lexical_match` when both lists rank
+/// the row equally, and up to `lexical_match²` as the FTS5 index dominates. A row the vector
+/// search missed counts as ranked just past the end of its list.
+pub fn fusion_weight(fused: &FusedRank, vector_depth: usize, k: f64, lexical_match: f64) -> f64 {
+    let Some(lexical_rank) = fused.lexical_rank else {
+        return 1.0;
+    };
+    let lexical_score = 1.0 / (k + lexical_rank as f64);
+    let score = match fused.vector_rank {
+        Some(_) => fused.score,
+        None => fused.score + 1.0 / (k + (vector_depth + 1) as f64),
+    };
+    lexical_match.powf(2.0 * lexical_score / score)
+}
+
+fn format_fused_rank(fused: &FusedRank) -> String {
+    let show = |rank: Option<usize>| rank.map_or("-".to_string(), |r| format!("#{r}"));
+    format!(
+        "rrf_rank #{} (vector {}, lexical {})",
+        fused.rank,
+        show(fused.vector_rank),
+        show(fused.lexical_rank)
+    )
+}
+
+/// Marks each result with `$PATH` availability, applies the profile's `installed` weight,
+/// and optionally drops tools that aren't installed. Re-sorts when the weight changes scores.
+pub fn annotate_installed(
+    sorted: &mut Vec<(String, CmdData)>,
+    profile: &RankingProfile,
+    installed_only: bool,
+    is_installed: impl Fn(&str, Option<&str>) -> bool,
+) {
+    let weight = profile.weights.installed;
+    for (cmd, data) in sorted.iter_mut() {
+        data.installed = is_installed(cmd, data.family.as_ref().map(|f| f.family.as_str()));
+        if data.installed && weight != 1.0 {
+            data.adjusted_score *= weight;
+            data.heuristics.push(format!("installed ({weight}x)"));
+        }
+    }
+
+    if installed_only {
+        sorted.retain(|(_, data)| data.installed);
+    }
+
+    if weight != 1.0 {
+        sorted.sort_by(|a, b| {
+            a.1.adjusted_score
+                .partial_cmp(&b.1.adjusted_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+}
+
+/// Finds `tool-sub` pages that document a subcommand of an existing `tool` page.
+/// A page only counts when one of its examples is typed as `tool sub ...`; `apt-get` is its own
+/// binary (examples start with `apt-get`), `git-stash` is not (`git stash push`).
+pub fn derive_command_families(conn: &Connection) -> anyhow::Result<CommandFamilies> {
+    let mut stmt = conn.prepare("SELECT command, example_cmd FROM pages_vec")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let names: HashSet<&str> = rows.iter().map(|(cmd, _)| cmd.as_str()).collect();
+
+    let mut families = CommandFamilies::new();
+    for (cmd, example_cmd) in &rows {
+        let Some((parent, rest)) = cmd.split_once('-') else {
+            continue;
+        };
+        if families.contains_key(cmd) || rest.is_empty() || !names.contains(parent) {
+            continue;
+        }
+        let subcommand = rest.replace('-', " ");
+        let spaced = format!("{parent} {subcommand}");
+        let typed_as_subcommand = example_cmd
+            .trim_start()
+            .strip_prefix(&spaced)
+            .is_some_and(|after| after.is_empty() || after.starts_with(char::is_whitespace));
+        if typed_as_subcommand {
+            families.insert(
+                cmd.clone(),
+                CommandFamily {
+                    family: parent.to_string(),
+                    subcommand,
+                },
+            );
+        }
+    }
+    Ok(families)
+}
+
+/// Persists the importer's family table (`command_families`), replacing any previous one.
+pub fn store_command_families(conn: &Connection, families: &CommandFamilies) -> anyhow::Result<()> {
+    conn.execute("DROP TABLE IF EXISTS command_families", [])?;
+    conn.execute(
+        "CREATE TABLE command_families (
+            command TEXT PRIMARY KEY,
+            family TEXT NOT NULL,
+            subcommand TEXT NOT NULL
+        )",
+        [],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO command_families (command, family, subcommand) VALUES (?1, ?2, ?3)",
+    )?;
+    for (cmd, f) in families {
+        stmt.execute(params![cmd, f.family, f.subcommand])?;
+    }
+    Ok(())
+}
+
+/// Reads `command_families`. Databases built before the table existed derive it once and keep
+/// it; a read-only database derives it again next time.
+pub fn load_command_families(conn: &Connection) -> anyhow::Result<CommandFamilies> {
+    if !db::has_table(conn, "command_families")? {
+        let families = derive_command_families(conn)?;
+        let _ = store_command_families(conn, &families);
+        return Ok(families);
+    }
+    let mut stmt = conn.prepare("SELECT command, family, subcommand FROM command_families")?;
+    let families = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                CommandFamily {
+                    family: row.get(1)?,
+                    subcommand: row.get(2)?,
+                },
+            ))
+        })?
+        .collect::<Result<CommandFamilies, _>>()?;
+    Ok(families)
+}
+
+/// Commands a search is restricted to with `--tool`: each tool's page and its subcommand pages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolScope {
+    /// Requested tool names, normalized (`git stash` -> `git-stash`).
+    pub tools: Vec<String>,
+    pub commands: HashSet<String>,
+    /// Requested tools with no page in the database.
+    pub unknown: Vec<String>,
+}
+
+impl ToolScope {
+    pub fn contains(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+}
+
+/// Expands `--tool` names to the pages they cover. `git` covers `git` and every `git-*` page
+/// recorded as a subcommand; standalone binaries like `apt-get` stay out of `apt`.
+pub fn resolve_tool_scope(
+    tools: &[String],
+    commands: &[String],
+    families: &CommandFamilies,
+) -> ToolScope {
+    let mut scope = ToolScope::default();
+    for tool in tools {
+        let tool = tool
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        if tool.is_empty() || scope.tools.contains(&tool) {
+            continue;
+        }
+        if commands.iter().any(|c| c == &tool) {
+            let prefix = format!("{tool}-");
+            scope.commands.insert(tool.clone());
+            scope.commands.extend(
+                families
+                    .keys()
+                    .filter(|cmd| cmd.starts_with(&prefix))
+                    .cloned(),
+            );
+        } else {
+            scope.unknown.push(tool.clone());
+        }
+        scope.tools.push(tool);
+    }
+    scope
+}
+
+/// Brute-force nearest examples within a tool scope. A family is a few hundred rows at most,
+/// so exact distances are cheaper than widening the KNN and filtering afterwards.
+fn scoped_vector_search(
+    conn: &Connection,
+    q_blob: &[u8],
+    scope: &ToolScope,
+    target_os: TargetOs,
+    cross_platform: bool,
+    k: usize,
+) -> anyhow::Result<Vec<(i64, Candidate)>> {
+    let mut commands: Vec<&String> = scope.commands.iter().collect();
+    commands.sort();
+    if commands.is_empty() {
+        return Ok(vec![]);
+    }
+    let placeholders = (0..commands.len())
+        .map(|i| format!("?{}", i + 4))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT rowid, command, os, description, example_desc, example_cmd,
+                vec_distance_l2(embedding, ?1) AS distance
+         FROM pages_vec
+         WHERE command IN ({placeholders}) AND (?2 IS NULL OR os = 'common' OR os = ?2)
+         ORDER BY distance
+         LIMIT ?3;"
+    );
+    let os_filter = (!cross_platform).then(|| target_os.as_str());
+    let limit = k as i64;
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&q_blob, &os_filter, &limit];
+    values.extend(commands.iter().map(|c| *c as &dyn rusqlite::ToSql));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mapped = stmt.query_map(values.as_slice(), |row| {
+        let os: String = row.get(2)?;
+        Ok((
+            row.get::<_, i64>(0)?,
+            Candidate {
+                command: row.get(1)?,
+                os: if cross_platform {
+                    os
+                } else {
+                    target_os.as_str().to_string()
+                },
+                description: row.get(3)?,
+                example_desc: row.get(4)?,
+                example_cmd: row.get(5)?,
+                distance: row.get(6)?,
+            },
+        ))
+    })?;
+    Ok(mapped.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// A command's complete tldr page, read straight from the database (no embedding needed).
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub command: String,
+    pub platform: String,
+    pub description: String,
+    /// In page order.
+    pub examples: Vec<Example>,
+    /// `command-*` pages on the same platforms (`git` -> `git-commit`, ...).
+    pub variants: Vec<String>,
+}
+
+/// Every platform directory (`common`, `linux`, ...) that has a page named `command`.
+pub fn page_platforms(conn: &Connection, command: &str) -> anyhow::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT os FROM pages_vec WHERE command = ?1 ORDER BY os")?;
+    let platforms = stmt
+        .query_map(params![command], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(platforms)
+}
+
+/// Loads every example of `command` for the target OS, using the same `common` + OS filter as
+/// hydration. When both exist, the OS-specific page wins over the common one, as in tldr clients.
+pub fn lookup_page(
+    conn: &Connection,
+    command: &str,
+    target_os: TargetOs,
+) -> anyhow::Result<Option<Page>> {
+    let mut stmt = conn.prepare(
+        "SELECT os, description, example_desc, example_cmd
+         FROM pages_vec
+         WHERE command = ?1 AND (os = 'common' OR os = ?2)
+         ORDER BY rowid",
+    )?;
+    let rows = stmt
+        .query_map(params![command, target_os.as_str()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Some(platform) = rows
+        .iter()
+        .map(|(os, ..)| os.as_str())
+        .find(|os| *os == target_os.as_str())
+        .or_else(|| rows.first().map(|(os, ..)| os.as_str()))
+        .map(str::to_string)
+    else {
+        return Ok(None);
+    };
+
+    let mut description = String::new();
+    let mut examples: Vec<Example> = Vec::new();
+    for (os, desc, ex_desc, ex_cmd) in rows {
+        if os != platform {
+            continue;
+        }
+        if description.is_empty() {
+            description = desc;
+        }
+        if !examples
+            .iter()
+            .any(|e| e.description == ex_desc && e.syntax == ex_cmd)
+        {
+            examples.push(Example::new(ex_desc, ex_cmd));
+        }
+    }
+
+    let prefix = format!("{command}-");
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT command FROM pages_vec WHERE os = 'common' OR os = ?1")?;
+    let mut variants = stmt
+        .query_map(params![target_os.as_str()], |row| row.get::<_, String>(0))?
+        .filter(|name| name.as_ref().is_ok_and(|n| n.starts_with(&prefix)))
+        .collect::<Result<Vec<_>, _>>()?;
+    variants.sort();
+
+    Ok(Some(Page {
+        command: command.to_string(),
+        platform,
+        description,
+        examples,
+        variants,
+    }))
+}
+
+/// Raises example depth for thin top hits so the JSON output meets the policy guard.
+/// After exercises, consider tightening platform filtering or emitting per-example OS annotations.
+pub fn hydrate_top_result_examples(
+    conn: &Connection,
+    sorted: &mut [(String, CmdData)],
+    query: &str,
+    target_os: TargetOs,
+    cross_platform: bool,
+    min_examples: usize,
+    max_examples: usize,
+) -> anyhow::Result<usize> {
+    let Some((command, data)) = sorted.first_mut() else {
+        return Ok(0);
+    };
+
+    if data.examples.len() >= min_examples {
+        return Ok(0);
+    }
+
+    // Avoid enriching unrelated top hits; only hydrate when query clearly targets this command.
+    if !query_mentions_command_family(query, command) || !is_simple_intent_query(query) {
+        return Ok(0);
+    }
+
+    hydrate_examples_for_command(conn, command, data, target_os, cross_platform, max_examples)
+}
+
+fn query_mentions_command_family(query: &str, command: &str) -> bool {
+    let family = command.split('-').next().unwrap_or(command);
+    let query_words: Vec<String> = query
+        .split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .to_ascii_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let command_lc = command.to_ascii_lowercase();
+    let family_lc = family.to_ascii_lowercase();
+
+    query_words
+        .iter()
+        .any(|w| w == &command_lc || w == &family_lc)
+}
+
+fn is_simple_intent_query(query: &str) -> bool {
+    let q = query.to_ascii_lowercase();
+    // Multi-step intent is better handled by decomposition instead of auto-expanding one command.
+    if q.contains(" and ") || q.contains(" then ") || q.contains(';') || q.contains(" with ") {
+        return false;
+    }
+
+    let words = q.split_whitespace().count();
+    words <= 6
+}
+
+/// Splits a compound question into ordered single-intent sub-queries.
+/// `;`, `&&` and "then" always separate steps; a bare "and" only does when both sides carry
+/// enough terms to stand alone, so "search and replace text" stays one query.
+pub fn decompose_query(query: &str) -> Vec<String> {
+    let mut steps = Vec::new();
+    for part in split_on_any(
+        query,
+        &[";", "&&", ", and then ", " and then ", ", then ", " then "],
+    ) {
+        let mut rest = part.as_str();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let split_at = lower.match_indices(" and ").find_map(|(idx, sep)| {
+                let (left, right) = (&rest[..idx], &rest[idx + sep.len()..]);
+                let standalone =
+                    |s: &str| extract_intent_terms(s).len() >= DECOMPOSE_MIN_STEP_TERMS;
+                (standalone(left) && standalone(right)).then_some((idx, sep.len()))
+            });
+            match split_at {
+                Some((idx, len)) => {
+                    push_step(&rest[..idx], &mut steps);
+                    rest = &rest[idx + len..];
+                }
+                None => {
+                    push_step(rest, &mut steps);
+                    break;
+                }
+            }
+        }
+    }
+    steps
+}
+
+/// Case-insensitive split on several separators. ASCII lowercasing keeps byte offsets aligned.
+fn split_on_any(text: &str, separators: &[&str]) -> Vec<String> {
+    let lower = text.to_ascii_lowercase();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if let Some(sep) = separators.iter().find(|sep| lower[i..].starts_with(*sep)) {
+            parts.push(text[start..i].to_string());
+            i += sep.len();
+            start = i;
+        } else {
+            i += lower[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    parts.push(text[start..].to_string());
+    parts
+}
+
+fn push_step(raw: &str, steps: &mut Vec<String>) {
+    let mut step = raw.trim().trim_matches(|c: char| c == ',' || c == '.');
+    for lead in ["then ", "and "] {
+        if step.len() >= lead.len() && step[..lead.len()].eq_ignore_ascii_case(lead) {
+            step = step[lead.len()..].trim_start();
+        }
+    }
+    if !step.is_empty() {
+        steps.push(step.to_string());
+    }
+}
+
+pub fn evaluate_intent_coverage(
+    query: &str,
+    command: &str,
+    data: &CmdData,
+    profile: &RankingProfile,
+) -> IntentCoverage {
+    // Lightweight lexical coverage check used as an execution guard for partial semantic matches.
+    // Improvement idea after running the new tests: tune term filtering so short or stopword-heavy queries still give useful warnings.
+    let (constraint_terms, query_terms): (Vec<IntentTerm>, Vec<IntentTerm>) =
+        intent_terms(query).into_iter().partition(|t| t.negated);
+    let constraints: Vec<String> = constraint_terms
+        .iter()
+        .map(|t| t.original.clone())
+        .collect();
+
+    // Only the example an agent would run can contradict a constraint; the page as a whole
+    // may well mention compression while its best example does not compress.
+    let violated_constraints: Vec<String> = match data.examples.first() {
+        Some(best) => {
+            let asserted: HashSet<String> =
+                intent_terms(&format!("{} {}", best.description, best.syntax))
+                    .into_iter()
+                    .filter(|t| !t.negated)
+                    .map(|t| t.normalized)
+                    .collect();
+            constraint_terms
+                .iter()
+                .filter(|t| asserted.contains(&t.normalized))
+                .map(|t| t.original.clone())
+                .collect()
+        }
+        None => vec![],
+    };
+
+    if query_terms.is_empty() {
+        return IntentCoverage {
+            score: 0.0,
+            matched_terms: vec![],
+            missing_terms: vec![],
+            constraints,
+            violated_constraints,
+            strong: false,
+        };
+    }
+
+    let mut corpus = String::new();
+    corpus.push_str(command);
+    corpus.push(' ');
+    corpus.push_str(&data.description);
+    corpus.push(' ');
+    for example in &data.examples {
+        corpus.push_str(&example.description);
+        corpus.push(' ');
+        corpus.push_str(&example.syntax);
+        corpus.push(' ');
+    }
+
+    // Match on normalized forms but report the words the user actually typed.
+    let corpus_set: HashSet<String> = intent_terms(&corpus)
+        .into_iter()
+        .map(|t| t.normalized)
+        .collect();
+
+    let mut matched_terms = Vec::new();
+    let mut missing_terms = Vec::new();
+    for term in query_terms.iter() {
+        if corpus_set.contains(&term.normalized) {
+            matched_terms.push(term.original.clone());
+        } else {
+            missing_terms.push(term.original.clone());
+        }
+    }
+
+    let score = matched_terms.len() as f64 / query_terms.len() as f64;
+    // Demand higher coverage for longer queries because they encode more explicit constraints.
+    let thresholds = &profile.intent;
+    let min_score = if query_terms.len() >= thresholds.complex_min_terms {
+        thresholds.min_score_complex
+    } else {
+        thresholds.min_score_simple
+    };
+
+    IntentCoverage {
+        score,
+        matched_terms,
+        missing_terms,
+        strong: score >= min_score && violated_constraints.is_empty(),
+        constraints,
+        violated_constraints,
+    }
+}
+
+/// A query word as typed, plus the form used for matching.
+#[derive(Debug, Clone, PartialEq)]
+struct IntentTerm {
+    original: String,
+    normalized: String,
+    /// Falls inside a negation ("skip hidden"): a constraint, not something to cover.
+    negated: bool,
+}
+
+/// NFKC + Unicode lowercase, then English stemming for plain ASCII words
+/// ("Deleting" and "delete" both become "delet"). Other scripts keep their folded form.
+fn normalize_term(word: &str, stemmer: &Stemmer) -> String {
+    let folded: String = word.nfkc().flat_map(char::to_lowercase).collect();
+    if folded.chars().all(|c| c.is_ascii_alphabetic()) {
+        stemmer.stem(&folded).into_owned()
+    } else {
+        folded
+    }
+}
+
+fn extract_intent_terms(text: &str) -> Vec<String> {
+    intent_terms(text).into_iter().map(|t| t.original).collect()
+}
+
+fn intent_terms(text: &str) -> Vec<IntentTerm> {
+    let stemmer = Stemmer::create(Algorithm::English);
+    let mut terms = Vec::new();
+    let mut seen = HashSet::new();
+    // Set by a negation cue, cleared once the negated word (or a clause break) is reached.
+    let mut negating = false;
+
+    for raw in text.split_whitespace() {
+        let ends_clause = raw.ends_with([',', ';', ':', '.', '!', '?']);
+        let token = raw
+            .trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '\'')
+            .trim_matches('\'')
+            .to_lowercase();
+
+        if NEGATION_CUES.contains(&token.as_str()) {
+            negating = !ends_clause;
+            continue;
+        }
+        if CLAUSE_BREAKS.contains(&token.as_str()) {
+            negating = false;
+            continue;
+        }
+        if token.is_empty()
+            || INTENT_STOPWORDS.contains(&token.as_str())
+            || (negating && NEGATION_FILLERS.contains(&token.as_str()))
+        {
+            negating &= !ends_clause;
+            continue;
+        }
+
+        let mut pushed = false;
+        if !token.is_ascii() {
+            // Scripts written without spaces (CJK) still break into words here.
+            for word in token.unicode_words() {
+                pushed |= push_intent_term(word, negating, &stemmer, &mut terms, &mut seen);
+            }
+        } else {
+            pushed |= push_intent_term(&token, negating, &stemmer, &mut terms, &mut seen);
+            if token.contains('-') {
+                for part in token.split('-') {
+                    pushed |= push_intent_term(part, negating, &stemmer, &mut terms, &mut seen);
+                }
+            }
+        }
+        negating &= !pushed && !ends_clause;
+    }
+
+    terms
+}
+
+/// Returns whether the token produced a term.
+fn push_intent_term(
+    token: &str,
+    negated: bool,
+    stemmer: &Stemmer,
+    terms: &mut Vec<IntentTerm>,
+    seen: &mut HashSet<String>,
+) -> bool {
+    // Single ASCII characters carry no intent; a single ideograph can.
+    if token.is_empty() || (token.len() < 2 && token.is_ascii()) {
+        return false;
+    }
     if INTENT_STOPWORDS.contains(&token) {
-        return;
+        return false;
     }
-    if seen.insert(token.to_string()) {
-        terms.push(token.to_string());
+    let normalized = normalize_term(token, stemmer);
+    if seen.insert(normalized.clone()) {
+        terms.push(IntentTerm {
+            original: token.to_string(),
+            normalized,
+            negated,
+        });
     }
+    true
 }
 
 fn hydrate_examples_for_command(
@@ -478,7 +1595,11 @@ fn hydrate_examples_for_command(
         return Ok(0);
     }
 
-    let mut seen: HashSet<(String, String)> = data.examples.iter().cloned().collect();
+    let mut seen: HashSet<(String, String)> = data
+        .examples
+        .iter()
+        .map(|e| (e.description.clone(), e.syntax.clone()))
+        .collect();
     let mut added = 0usize;
 
     if cross_platform {
@@ -508,7 +1629,7 @@ fn hydrate_examples_for_command(
                 if data.description.is_empty() {
                     data.description = desc;
                 }
-                data.examples.push((ex_desc, ex_cmd));
+                data.examples.push(Example::new(ex_desc, ex_cmd));
                 added += 1;
             }
         }
@@ -537,7 +1658,7 @@ fn hydrate_examples_for_command(
                 if data.description.is_empty() {
                     data.description = desc;
                 }
-                data.examples.push((ex_desc, ex_cmd));
+                data.examples.push(Example::new(ex_desc, ex_cmd));
                 added += 1;
             }
         }
@@ -555,73 +1676,217 @@ mod tests {
 
     #[test]
     fn test_explicit_linux_flag() {
-        assert_eq!(get_target_os(true, false, false), TargetOs::Linux);
+        assert_eq!(
+            get_target_os(true, false, false),
+            (TargetOs::Linux, OsSource::Flag)
+        );
     }
 
     #[test]
     fn test_explicit_osx_flag() {
-        assert_eq!(get_target_os(false, true, false), TargetOs::Osx);
+        assert_eq!(
+            get_target_os(false, true, false),
+            (TargetOs::Osx, OsSource::Flag)
+        );
+    }
+
+    #[test]
+    fn test_explicit_windows_flag() {
+        assert_eq!(
+            get_target_os(false, false, true),
+            (TargetOs::Windows, OsSource::Flag)
+        );
     }
 
-    #[test]
-    fn test_explicit_windows_flag() {
-        assert_eq!(get_target_os(false, false, true), TargetOs::Windows);
+    #[test]
+    fn test_linux_takes_priority_over_osx() {
+        assert_eq!(
+            get_target_os(true, true, false),
+            (TargetOs::Linux, OsSource::Flag)
+        );
+    }
+
+    #[test]
+    fn test_no_flag_detects_host() {
+        assert_eq!(
+            get_target_os(false, false, false),
+            (detect_host_os(std::env::consts::OS), OsSource::Detected)
+        );
+    }
+
+    #[test]
+    fn test_resolve_named_os() {
+        assert_eq!(
+            resolve_target_os(Some("macos")).unwrap(),
+            (TargetOs::Osx, OsSource::Flag)
+        );
+        assert_eq!(
+            resolve_target_os(None).unwrap(),
+            get_target_os(false, false, false)
+        );
+        assert!(resolve_target_os(Some("plan9")).is_err());
+    }
+
+    #[test]
+    fn test_detect_host_os_every_platform() {
+        assert_eq!(detect_host_os("linux"), TargetOs::Linux);
+        assert_eq!(detect_host_os("macos"), TargetOs::Osx);
+        assert_eq!(detect_host_os("ios"), TargetOs::Osx);
+        assert_eq!(detect_host_os("windows"), TargetOs::Windows);
+        assert_eq!(detect_host_os("freebsd"), TargetOs::FreeBsd);
+        assert_eq!(detect_host_os("dragonfly"), TargetOs::FreeBsd);
+        assert_eq!(detect_host_os("openbsd"), TargetOs::OpenBsd);
+        assert_eq!(detect_host_os("netbsd"), TargetOs::NetBsd);
+        assert_eq!(detect_host_os("illumos"), TargetOs::Linux);
+        assert_eq!(detect_host_os("android"), TargetOs::Linux);
+    }
+
+    #[test]
+    fn bsd_targets_without_pages_read_linux_pages() {
+        let conn = test_conn();
+        conn.execute(
+            "INSERT INTO pages_vec(command, os, description, example_desc, example_cmd)
+             VALUES ('sockstat', 'freebsd', 'List open sockets.', 'List sockets', 'sockstat')",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(
+            page_os(&conn, TargetOs::FreeBsd).unwrap(),
+            TargetOs::FreeBsd
+        );
+        assert_eq!(page_os(&conn, TargetOs::OpenBsd).unwrap(), TargetOs::Linux);
+        assert_eq!(page_os(&conn, TargetOs::NetBsd).unwrap(), TargetOs::Linux);
+        assert_eq!(page_os(&conn, TargetOs::Osx).unwrap(), TargetOs::Osx);
     }
 
     #[test]
-    fn test_linux_takes_priority_over_osx() {
-        assert_eq!(get_target_os(true, true, false), TargetOs::Linux);
+    fn test_os_source_labels() {
+        assert_eq!(OsSource::Detected.as_str(), "detected");
+        assert_eq!(OsSource::Flag.as_str(), "flag");
+        assert_eq!(TargetOs::FreeBsd.as_str(), "freebsd");
     }
 
     // --- adjust_score ---
 
     #[test]
     fn test_filters_out_high_distance() {
-        assert!(adjust_score("dummy query", "ls", "list files", 1.50).is_none());
-        assert!(adjust_score("dummy query", "ls", "list files", 1.11).is_none());
+        assert!(
+            adjust_score(
+                "dummy query",
+                "ls",
+                "list files",
+                1.50,
+                &RankingProfile::default(),
+                None
+            )
+            .is_none()
+        );
+        assert!(
+            adjust_score(
+                "dummy query",
+                "ls",
+                "list files",
+                1.11,
+                &RankingProfile::default(),
+                None
+            )
+            .is_none()
+        );
     }
 
     #[test]
     fn test_accepts_low_distance() {
-        assert!(adjust_score("dummy query", "ls", "list files", 0.5).is_some());
+        assert!(
+            adjust_score(
+                "dummy query",
+                "ls",
+                "list files",
+                0.5,
+                &RankingProfile::default(),
+                None
+            )
+            .is_some()
+        );
     }
 
     #[test]
     fn test_core_command_boosted() {
-        let (ls_score, _) = adjust_score("dummy query", "ls", "list files", 0.5).unwrap();
+        let (ls_score, _) = adjust_score(
+            "dummy query",
+            "ls",
+            "list files",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         // 'ls' is in CORE_COMMANDS -> boosted by 0.67x (lower distance)
         assert!((ls_score - 0.335).abs() < 0.001);
     }
 
     #[test]
     fn test_grep_boosted() {
-        let (grep_score, _) = adjust_score("dummy query", "grep", "search patterns", 0.5).unwrap();
+        let (grep_score, _) = adjust_score(
+            "dummy query",
+            "grep",
+            "search patterns",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         // 'grep' is in CORE_COMMANDS -> boosted by 0.67x
         assert!((grep_score - 0.335).abs() < 0.001);
     }
 
     #[test]
     fn test_niche_variant_penalized() {
-        let (zgrep_score, _) =
-            adjust_score("dummy query", "zgrep", "search compressed", 0.5).unwrap();
+        let (zgrep_score, _) = adjust_score(
+            "dummy query",
+            "zgrep",
+            "search compressed",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         // starts with 'z' AND ends with "grep" AND not "grep" -> penalized by 1.33x
         assert!((zgrep_score - 0.665).abs() < 0.001);
     }
 
     #[test]
     fn test_hyphenated_command_penalized() {
-        let (score, _) = adjust_score("dummy query", "docker-cp", "copy files", 0.5).unwrap();
+        let (score, _) = adjust_score(
+            "dummy query",
+            "docker-cp",
+            "copy files",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         assert!((score - 0.665).abs() < 0.001);
     }
 
     #[test]
     fn test_official_site_boosts_score() {
-        let (plain, _) = adjust_score("dummy query", "find", "find files", 0.5).unwrap();
+        let (plain, _) = adjust_score(
+            "dummy query",
+            "find",
+            "find files",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         let (official, _) = adjust_score(
             "dummy query",
             "find",
             "find files. More information: gnu.org",
             0.5,
+            &RankingProfile::default(),
+            None,
         )
         .unwrap();
         assert!(official < plain); // lower distance is better
@@ -630,17 +1895,61 @@ mod tests {
     #[test]
     fn test_normal_command_no_modifier() {
         // 'randomtool' is not in CORE_COMMANDS, no special prefix/suffix -> no boost/penalty
-        let (score, _) = adjust_score("dummy query", "randomtool", "transfer data", 0.5).unwrap();
+        let (score, _) = adjust_score(
+            "dummy query",
+            "randomtool",
+            "transfer data",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         assert!((score - 0.5).abs() < 0.001);
     }
 
     #[test]
     fn test_exact_match_query() {
         // if user types "tar file", 'tar' gets a 0.5x exact match boost AND 0.67x core boost
-        let (score, _) = adjust_score("tar file", "tar", "archive utility", 0.5).unwrap();
+        let (score, _) = adjust_score(
+            "tar file",
+            "tar",
+            "archive utility",
+            0.5,
+            &RankingProfile::default(),
+            None,
+        )
+        .unwrap();
         assert!((score - (0.5 * 0.5 * 0.67)).abs() < 0.001);
     }
 
+    #[test]
+    fn test_profile_overrides_core_commands_and_weights() {
+        let mut profile = RankingProfile::default();
+        profile.core_commands.push("podman".to_string());
+        profile.weights.core_command = 0.5;
+
+        let (score, heuristics) = adjust_score(
+            "dummy query",
+            "podman",
+            "manage containers",
+            0.5,
+            &profile,
+            None,
+        )
+        .unwrap();
+        assert!((score - 0.25).abs() < 0.001);
+        assert_eq!(heuristics, vec!["core_command (0.5x)".to_string()]);
+    }
+
+    #[test]
+    fn test_profile_max_distance_filters() {
+        let profile = RankingProfile {
+            max_distance: 0.4,
+            ..RankingProfile::default()
+        };
+        assert!(adjust_score("dummy query", "ls", "list files", 0.5, &profile, None).is_none());
+    }
+
     fn test_conn() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
         conn.execute(
@@ -672,17 +1981,11 @@ mod tests {
 
         let mut sorted = vec![(
             "ssh".to_string(),
-            CmdData {
-                description: "Secure shell.".to_string(),
-                platform: "common".to_string(),
-                examples: vec![(
-                    "Dynamic forward".to_string(),
-                    "ssh -D 1080 user@host".to_string(),
-                )],
-                adjusted_score: 0.1,
-                raw_distance: 0.1,
-                heuristics: vec![],
-            },
+            CmdData::fixture(
+                "Secure shell.",
+                vec![Example::new("Dynamic forward", "ssh -D 1080 user@host")],
+                0.1,
+            ),
         )];
 
         let added = hydrate_top_result_examples(
@@ -715,14 +2018,11 @@ mod tests {
 
         let mut sorted = vec![(
             "tool".to_string(),
-            CmdData {
-                description: "desc".to_string(),
-                platform: "common".to_string(),
-                examples: vec![("Common example".to_string(), "tool --common".to_string())],
-                adjusted_score: 0.1,
-                raw_distance: 0.1,
-                heuristics: vec![],
-            },
+            CmdData::fixture(
+                "desc",
+                vec![Example::new("Common example", "tool --common")],
+                0.1,
+            ),
         )];
 
         let added = hydrate_top_result_examples(
@@ -740,7 +2040,7 @@ mod tests {
             .1
             .examples
             .iter()
-            .map(|(_, cmd)| cmd.as_str())
+            .map(|e| e.syntax.as_str())
             .collect();
 
         assert_eq!(added, 1);
@@ -764,12 +2064,12 @@ mod tests {
         let mut sorted = vec![(
             "dnsrecon".to_string(),
             CmdData {
-                description: "desc".to_string(),
                 platform: "linux".to_string(),
-                examples: vec![("Example 1".to_string(), "dnsrecon --help".to_string())],
-                adjusted_score: 0.1,
-                raw_distance: 0.1,
-                heuristics: vec![],
+                ..CmdData::fixture(
+                    "desc",
+                    vec![Example::new("Example 1", "dnsrecon --help")],
+                    0.1,
+                )
             },
         )];
 
@@ -803,14 +2103,7 @@ mod tests {
 
         let mut sorted = vec![(
             "awk".to_string(),
-            CmdData {
-                description: "desc".to_string(),
-                platform: "common".to_string(),
-                examples: vec![("Example 1".to_string(), "awk --help".to_string())],
-                adjusted_score: 0.1,
-                raw_distance: 0.1,
-                heuristics: vec![],
-            },
+            CmdData::fixture("desc", vec![Example::new("Example 1", "awk --help")], 0.1),
         )];
 
         let added = hydrate_top_result_examples(
@@ -834,37 +2127,154 @@ mod tests {
         assert!(terms.contains(&"ls".to_string()));
     }
 
+    #[test]
+    fn intent_terms_match_inflected_forms() {
+        let data = CmdData::fixture(
+            "Delete files.",
+            vec![Example::new(
+                "Remove an archive",
+                "rm {{path/to/archive.tar}}",
+            )],
+            0.1,
+        );
+
+        let coverage = evaluate_intent_coverage(
+            "Deleting old Archives",
+            "rm",
+            &data,
+            &RankingProfile::default(),
+        );
+        // Original words are reported, not their stems.
+        assert_eq!(coverage.matched_terms, vec!["deleting", "archives"]);
+        assert_eq!(coverage.missing_terms, vec!["old"]);
+    }
+
+    #[test]
+    fn intent_terms_keep_non_english_words() {
+        let terms = extract_intent_terms("Lösche alte Dateien");
+        assert_eq!(terms, vec!["lösche", "alte", "dateien"]);
+
+        // CJK has no spaces; words still come out individually.
+        let terms = extract_intent_terms("压缩文件");
+        assert!(terms.len() > 1);
+
+        // Full-width letters fold to ASCII before stemming.
+        let stemmer = Stemmer::create(Algorithm::English);
+        assert_eq!(normalize_term("ＤＥＬＥＴＩＮＧ", &stemmer), "delet");
+    }
+
+    #[test]
+    fn negated_terms_become_constraints() {
+        let terms = intent_terms("copy files but skip hidden ones, without any compression");
+        let negated: Vec<&str> = terms
+            .iter()
+            .filter(|t| t.negated)
+            .map(|t| t.original.as_str())
+            .collect();
+        let positive: Vec<&str> = terms
+            .iter()
+            .filter(|t| !t.negated)
+            .map(|t| t.original.as_str())
+            .collect();
+        assert_eq!(negated, vec!["hidden", "compression"]);
+        assert_eq!(positive, vec!["copy", "files", "ones"]);
+    }
+
+    #[test]
+    fn example_asserting_a_negated_term_is_a_violation() {
+        let compressing = CmdData::fixture(
+            "Archiving utility.",
+            vec![
+                Example::new(
+                    "Create a gzipped archive using compression",
+                    "tar czf {{target.tar.gz}} {{file}}",
+                ),
+                Example::new("Create an archive", "tar cf {{target.tar}} {{file}}"),
+            ],
+            0.1,
+        );
+        let coverage = evaluate_intent_coverage(
+            "tar archive without compression",
+            "tar",
+            &compressing,
+            &RankingProfile::default(),
+        );
+        assert_eq!(coverage.constraints, vec!["compression"]);
+        assert_eq!(coverage.violated_constraints, vec!["compression"]);
+        assert_eq!(coverage.score, 1.0);
+        assert!(!coverage.strong);
+        // The negated word is neither covered nor missing.
+        assert!(!coverage.missing_terms.contains(&"compression".to_string()));
+
+        let plain = CmdData::fixture(
+            "Archiving utility, with optional compression.",
+            vec![
+                Example::new("Create an archive", "tar cf {{target.tar}} {{file}}"),
+                Example::new(
+                    "Create a gzipped archive using compression",
+                    "tar czf {{target.tar.gz}} {{file}}",
+                ),
+            ],
+            0.1,
+        );
+        let coverage = evaluate_intent_coverage(
+            "tar archive without compression",
+            "tar",
+            &plain,
+            &RankingProfile::default(),
+        );
+        assert!(coverage.violated_constraints.is_empty());
+        assert!(coverage.strong);
+    }
+
+    #[test]
+    fn example_that_also_negates_is_not_a_violation() {
+        let data = CmdData::fixture(
+            "Copy files.",
+            vec![Example::new(
+                "Copy a directory, skip hidden files",
+                "rsync -a --exclude='.*' {{src}} {{dst}}",
+            )],
+            0.1,
+        );
+        let coverage = evaluate_intent_coverage(
+            "copy files but skip hidden",
+            "rsync",
+            &data,
+            &RankingProfile::default(),
+        );
+        assert_eq!(coverage.constraints, vec!["hidden"]);
+        assert!(coverage.violated_constraints.is_empty());
+        assert!(coverage.strong);
+    }
+
     #[test]
     fn empty_intent_marks_as_weak() {
-        let data = CmdData {
-            description: "desc".to_string(),
-            platform: "common".to_string(),
-            examples: vec![],
-            adjusted_score: 0.1,
-            raw_distance: 0.1,
-            heuristics: vec![],
-        };
+        let data = CmdData::fixture("desc", vec![], 0.1);
 
-        let coverage = evaluate_intent_coverage("and the with", "cmd", &data);
+        let coverage =
+            evaluate_intent_coverage("and the with", "cmd", &data, &RankingProfile::default());
         assert_eq!(coverage.score, 0.0);
         assert!(!coverage.strong);
     }
 
     #[test]
     fn intent_coverage_detects_missing_sub_intent_terms() {
-        let data = CmdData {
-            description: "Run ad-hoc ansible commands.".to_string(),
-            platform: "common".to_string(),
-            examples: vec![(
-                "Run command on group".to_string(),
-                "ansible group -m command -a 'uptime'".to_string(),
+        let data = CmdData::fixture(
+            "Run ad-hoc ansible commands.",
+            vec![Example::new(
+                "Run command on group",
+                "ansible group -m command -a 'uptime'",
             )],
-            adjusted_score: 0.1,
-            raw_distance: 0.1,
-            heuristics: vec![],
-        };
-
-        let coverage = evaluate_intent_coverage("run ansible playbook with tags", "ansible", &data);
+            0.1,
+        );
+
+        let coverage = evaluate_intent_coverage(
+            "run ansible playbook with tags",
+            "ansible",
+            &data,
+            &RankingProfile::default(),
+        );
         assert!(coverage.score < 0.60);
         assert!(!coverage.strong);
         assert!(coverage.missing_terms.contains(&"playbook".to_string()));
@@ -873,21 +2283,745 @@ mod tests {
 
     #[test]
     fn intent_coverage_passes_for_specific_match() {
-        let data = CmdData {
-            description: "Run playbooks.".to_string(),
-            platform: "common".to_string(),
-            examples: vec![(
-                "Run with tags".to_string(),
-                "ansible-playbook site.yml --tags web".to_string(),
+        let data = CmdData::fixture(
+            "Run playbooks.",
+            vec![Example::new(
+                "Run with tags",
+                "ansible-playbook site.yml --tags web",
             )],
-            adjusted_score: 0.1,
-            raw_distance: 0.1,
-            heuristics: vec![],
-        };
-
-        let coverage =
-            evaluate_intent_coverage("run ansible playbook with tags", "ansible-playbook", &data);
+            0.1,
+        );
+
+        let coverage = evaluate_intent_coverage(
+            "run ansible playbook with tags",
+            "ansible-playbook",
+            &data,
+            &RankingProfile::default(),
+        );
         assert!(coverage.score >= 0.60);
         assert!(coverage.strong);
     }
+
+    // --- hybrid retrieval ---
+
+    #[test]
+    fn fts_query_quotes_terms_and_strips_flag_dashes() {
+        let q = build_fts_query("rsync --partial to the server").unwrap();
+        assert_eq!(q, "\"rsync\" OR \"partial\" OR \"server\"");
+    }
+
+    #[test]
+    fn fts_query_empty_for_stopwords_only() {
+        assert!(build_fts_query("how to --").is_none());
+    }
+
+    #[test]
+    fn rrf_promotes_rows_found_by_both_retrievers() {
+        let fused = fuse_rankings(&[1, 2, 3], &[3, 4], RRF_K);
+        assert_eq!(fused[0].rowid, 3);
+        assert_eq!(fused[0].rank, 1);
+        assert_eq!(fused[0].vector_rank, Some(3));
+        assert_eq!(fused[0].lexical_rank, Some(1));
+        assert_eq!(fused.len(), 4);
+        assert_eq!(fused[1].rowid, 1);
+        // Vector order breaks ties between single-list rows at the same rank.
+        assert_eq!(fused[2].rowid, 2);
+        assert_eq!(fused[3].rowid, 4);
+    }
+
+    #[test]
+    fn lexical_search_filters_by_os_and_skips_missing_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert!(
+            lexical_search(&conn, "xargs -0", TargetOs::Linux, false, KNN_BASE_K)
+                .unwrap()
+                .is_empty()
+        );
+
+        conn.execute(
+            "CREATE VIRTUAL TABLE pages_fts USING fts5(
+                command, description, example_desc, example_cmd, os UNINDEXED
+            )",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO pages_fts(rowid, command, description, example_desc, example_cmd, os)
+             VALUES
+             (1, 'xargs', 'Execute a command.', 'Handle null-separated input', 'xargs -0 rm', 'common'),
+             (2, 'tool', 'Something else.', 'Run it', 'tool --run', 'common'),
+             (3, 'xargs', 'Execute a command.', 'BSD variant', 'xargs -0 -n1 echo', 'osx')",
+            [],
+        )
+        .unwrap();
+
+        let hits = lexical_search(&conn, "xargs -0", TargetOs::Linux, false, KNN_BASE_K).unwrap();
+        assert_eq!(hits, vec![1]);
+
+        let hits = lexical_search(&conn, "xargs -0", TargetOs::Linux, true, KNN_BASE_K).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(!hits.contains(&2));
+        let hits = lexical_search(&conn, "xargs -0", TargetOs::Linux, true, 1).unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    fn hybrid_conn() -> Connection {
+        #[allow(clippy::missing_transmute_annotations)]
+        unsafe {
+            rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            )));
+        }
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE VIRTUAL TABLE pages_vec USING vec0(
+                command TEXT,
+                os TEXT,
+                description TEXT,
+                example_desc TEXT,
+                example_cmd TEXT,
+                embedding FLOAT[2]
+            )",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "CREATE VIRTUAL TABLE pages_fts USING fts5(
+                command, description, example_desc, example_cmd, os UNINDEXED
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn insert_hybrid_row(conn: &Connection, rowid: i64, row: [&str; 5], embedding: [f32; 2]) {
+        let [command, os, desc, ex_desc, ex_cmd] = row;
+        conn.execute(
+            "INSERT INTO pages_vec(rowid, command, os, description, example_desc, example_cmd, embedding)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![rowid, command, os, desc, ex_desc, ex_cmd, embedding.as_bytes()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO pages_fts(rowid, command, description, example_desc, example_cmd, os)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![rowid, command, desc, ex_desc, ex_cmd, os],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn perform_search_fuses_lexical_hits_into_ranking() {
+        let conn = hybrid_conn();
+        insert_hybrid_row(
+            &conn,
+            1,
+            [
+                "cp",
+                "common",
+                "Copy files.",
+                "Copy a file",
+                "cp {{src}} {{dst}}",
+            ],
+            [1.0, 0.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            2,
+            [
+                "rsync",
+                "common",
+                "Transfer files.",
+                "Keep partially transferred files",
+                "rsync --partial {{src}} {{dst}}",
+            ],
+            [0.6, 0.8],
+        );
+
+        let sorted = perform_search(
+            &conn,
+            "resume --partial",
+            &[1.0, 0.0],
+            TargetOs::Linux,
+            false,
+            &RankingProfile::default(),
+            &QueryContext {
+                corrections: vec![],
+                families: &CommandFamilies::new(),
+                scope: None,
+                min_commands: 0,
+            },
+        )
+        .unwrap();
+
+        let rsync = sorted.iter().find(|(cmd, _)| cmd == "rsync").unwrap();
+        assert!((rsync.1.raw_distance - 0.8f64.sqrt()).abs() < 0.001);
+        assert!(
+            rsync
+                .1
+                .heuristics
+                .iter()
+                .any(|h| h.starts_with("rrf_rank #1 (vector #2, lexical #1)"))
+        );
+        assert!(
+            rsync
+                .1
+                .heuristics
+                .iter()
+                .any(|h| h.starts_with("lexical_match"))
+        );
+    }
+
+    #[test]
+    fn lexical_only_hit_outranks_weaker_vector_hit() {
+        let conn = hybrid_conn();
+        // A chatty page fills the whole first KNN pass at distance 0.9.
+        for rowid in 1..=KNN_BASE_K as i64 {
+            insert_hybrid_row(
+                &conn,
+                rowid,
+                [
+                    "hexyl",
+                    "common",
+                    "Display file contents in hexadecimal.",
+                    "Show a file",
+                    "hexyl {{path/to/file}}",
+                ],
+                [0.595, 0.804],
+            );
+        }
+        insert_hybrid_row(
+            &conn,
+            100,
+            [
+                "xxd",
+                "common",
+                "Create a hexdump.",
+                "Revert a plain dump into binary",
+                "xxd -r -p {{path/to/input}}",
+            ],
+            [0.5, 0.866],
+        );
+
+        let context = QueryContext {
+            corrections: vec![],
+            families: &CommandFamilies::new(),
+            scope: None,
+            min_commands: 0,
+        };
+        let sorted = perform_search(
+            &conn,
+            "revert plain dump",
+            &[1.0, 0.0],
+            TargetOs::Linux,
+            false,
+            &RankingProfile::default(),
+            &context,
+        )
+        .unwrap();
+
+        // xxd is further away and only the FTS5 index found it, but it wins the fusion.
+        assert_eq!(sorted[0].0, "xxd");
+        let xxd = &sorted[0].1;
+        assert!(xxd.raw_distance > sorted[1].1.raw_distance);
+        assert!(
+            xxd.heuristics
+                .iter()
+                .any(|h| h.ends_with("(vector -, lexical #1)"))
+        );
+
+        let fused = fuse_rankings(&[1], &[2], RRF_K);
+        assert_eq!(fusion_weight(&fused[0], 1, RRF_K, 0.85), 1.0);
+        assert!(fusion_weight(&fused[1], 1, RRF_K, 0.85) < 0.85);
+        let both = fuse_rankings(&[2], &[2], RRF_K);
+        assert!((fusion_weight(&both[0], 1, RRF_K, 0.85) - 0.85).abs() < 1e-9);
+    }
+
+    #[test]
+    fn misspelled_command_names_are_corrected_and_boosted() {
+        let conn = hybrid_conn();
+        insert_hybrid_row(
+            &conn,
+            1,
+            [
+                "cp",
+                "common",
+                "Copy files.",
+                "Copy a file",
+                "cp {{src}} {{dst}}",
+            ],
+            [0.8, 0.2],
+        );
+        insert_hybrid_row(
+            &conn,
+            2,
+            [
+                "rsync",
+                "common",
+                "Transfer files.",
+                "Copy to a remote host",
+                "rsync {{src}} {{host}}:{{dst}}",
+            ],
+            [0.9, 0.1],
+        );
+
+        let commands = load_command_names(&conn).unwrap();
+        assert_eq!(commands, vec!["cp", "rsync"]);
+        assert!(corpus_contains(&conn, "remote").unwrap());
+        assert!(!corpus_contains(&conn, "rsycn").unwrap());
+
+        let query = "rsycn files to remote";
+        let corrections = correct_query_terms(&conn, query, &commands).unwrap();
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].corrected, "rsync");
+
+        let profile = RankingProfile::default();
+        let sorted = perform_search(
+            &conn,
+            query,
+            &[1.0, 0.0],
+            TargetOs::Linux,
+            false,
+            &profile,
+            &QueryContext {
+                corrections: corrections.clone(),
+                families: &CommandFamilies::new(),
+                scope: None,
+                min_commands: 0,
+            },
+        )
+        .unwrap();
+        assert_eq!(sorted[0].0, "rsync");
+        assert!(
+            sorted[0]
+                .1
+                .heuristics
+                .contains(&"fuzzy_match (rsycn -> rsync, 0.68x)".to_string())
+        );
+        // Graded: weaker than an exact mention, stronger than nothing.
+        let weight = fuzzy_weight(&profile, &corrections[0]);
+        assert!(weight > profile.weights.exact_match && weight < 1.0);
+    }
+
+    #[test]
+    fn corpus_words_are_not_typos_without_fts_index() {
+        let conn = test_conn();
+        conn.execute(
+            "INSERT INTO pages_vec(command, os, description, example_desc, example_cmd)
+             VALUES
+             ('sed', 'common', 'Edit text in a scriptable manner.', 'Replace text', 'sed s/a/b/ {{file}}'),
+             ('test', 'common', 'Check file types.', 'Test if a file exists', 'test -e {{file}}'),
+             ('rsync', 'common', 'Transfer files.', 'Copy to a host', 'rsync {{src}} {{dst}}')",
+            [],
+        )
+        .unwrap();
+        let commands = load_command_names(&conn).unwrap();
+
+        assert!(corpus_contains(&conn, "TEXT").unwrap());
+        assert!(!corpus_contains(&conn, "te_t").unwrap());
+        assert!(
+            correct_query_terms(&conn, "replace text in a file", &commands)
+                .unwrap()
+                .is_empty()
+        );
+        let corrections = correct_query_terms(&conn, "rsycn files", &commands).unwrap();
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].corrected, "rsync");
+    }
+
+    #[test]
+    fn subcommand_pages_form_families_and_match_spaced_queries() {
+        let conn = hybrid_conn();
+        let rows = [
+            ("git", "Version control.", "git status", [0.6, 0.8]),
+            ("git-stash", "Stash changes.", "git stash pop", [0.9, 0.1]),
+            ("apt", "Package manager.", "apt install {{pkg}}", [0.0, 1.0]),
+            (
+                "apt-get",
+                "Legacy apt.",
+                "apt-get install {{pkg}}",
+                [0.0, 1.0],
+            ),
+            ("pop", "Pop tool.", "pop {{value}}", [0.6, 0.8]),
+        ];
+        for (i, (cmd, desc, ex_cmd, embedding)) in rows.into_iter().enumerate() {
+            insert_hybrid_row(
+                &conn,
+                i as i64 + 1,
+                [cmd, "common", desc, "Example", ex_cmd],
+                embedding,
+            );
+        }
+
+        let families = derive_command_families(&conn).unwrap();
+        let stash = CommandFamily {
+            family: "git".to_string(),
+            subcommand: "stash".to_string(),
+        };
+        assert_eq!(families.get("git-stash"), Some(&stash));
+        // apt-get is typed as its own binary, not `apt get`.
+        assert!(!families.contains_key("apt-get"));
+
+        // Older databases get the table on first load.
+        assert!(!db::has_table(&conn, "command_families").unwrap());
+        assert_eq!(load_command_families(&conn).unwrap(), families);
+        assert!(db::has_table(&conn, "command_families").unwrap());
+        assert_eq!(load_command_families(&conn).unwrap(), families);
+
+        assert!(stash.is_named_in("git stash pop"));
+        assert!(!stash.is_named_in("stash git changes"));
+        let profile = RankingProfile::default();
+        let (boosted, heuristics) = adjust_score(
+            "git stash pop",
+            "git-stash",
+            "Stash changes.",
+            0.5,
+            &profile,
+            Some(&stash),
+        )
+        .unwrap();
+        assert!(heuristics.iter().any(|h| h.starts_with("subcommand_match")));
+        assert!(!heuristics.iter().any(|h| h.starts_with("niche_variant")));
+        let (plain, _) = adjust_score(
+            "stash my changes",
+            "git-stash",
+            "Stash changes.",
+            0.5,
+            &profile,
+            None,
+        )
+        .unwrap();
+        assert!(boosted < plain);
+
+        let sorted = perform_search(
+            &conn,
+            "git stash pop",
+            &[1.0, 0.0],
+            TargetOs::Linux,
+            false,
+            &profile,
+            &QueryContext {
+                corrections: vec![],
+                families: &families,
+                scope: None,
+                min_commands: 0,
+            },
+        )
+        .unwrap();
+        assert_eq!(sorted[0].0, "git-stash");
+        assert_eq!(sorted[0].1.family.as_ref(), Some(&stash));
+    }
+
+    #[test]
+    fn tool_scope_restricts_ranking_to_the_family() {
+        let conn = hybrid_conn();
+        let rows = [
+            ("git", "linux", "git status", [0.6, 0.8]),
+            ("git-stash", "common", "git stash pop", [0.8, 0.6]),
+            ("apt", "common", "apt install {{pkg}}", [0.0, 1.0]),
+            ("apt-get", "common", "apt-get install {{pkg}}", [1.0, 0.0]),
+        ];
+        for (i, (cmd, os, ex_cmd, embedding)) in rows.into_iter().enumerate() {
+            insert_hybrid_row(
+                &conn,
+                i as i64 + 1,
+                [cmd, os, "Tool.", "Example", ex_cmd],
+                embedding,
+            );
+        }
+        let commands = load_command_names(&conn).unwrap();
+        let families = derive_command_families(&conn).unwrap();
+
+        let scope = resolve_tool_scope(
+            &["Git".to_string(), "apt".to_string(), "svn".to_string()],
+            &commands,
+            &families,
+        );
+        assert_eq!(scope.tools, vec!["git", "apt", "svn"]);
+        assert_eq!(scope.unknown, vec!["svn"]);
+        assert!(scope.contains("git-stash") && scope.contains("apt"));
+        assert!(!scope.contains("apt-get"));
+
+        let git_only = resolve_tool_scope(&["git".to_string()], &commands, &families);
+        let search = |target_os, cross_platform| {
+            perform_search(
+                &conn,
+                "install package",
+                &[1.0, 0.0],
+                target_os,
+                cross_platform,
+                &RankingProfile::default(),
+                &QueryContext {
+                    corrections: vec![],
+                    families: &families,
+                    scope: Some(&git_only),
+                    min_commands: 0,
+                },
+            )
+            .unwrap()
+            .into_iter()
+            .map(|(cmd, _)| cmd)
+            .collect::<Vec<_>>()
+        };
+        // apt-get is the nearest vector and the best lexical hit, but outside the scope.
+        assert_eq!(search(TargetOs::Linux, false), vec!["git-stash", "git"]);
+        assert_eq!(search(TargetOs::Osx, false), vec!["git-stash"]);
+        assert_eq!(search(TargetOs::Osx, true), vec!["git-stash", "git"]);
+    }
+
+    #[test]
+    fn knn_widens_until_enough_distinct_commands() {
+        let conn = hybrid_conn();
+        // One page with more examples than the first KNN pass returns.
+        for i in 0..KNN_BASE_K as i64 + 5 {
+            insert_hybrid_row(
+                &conn,
+                i + 1,
+                [
+                    "chatty",
+                    "common",
+                    "Talks a lot.",
+                    "Example",
+                    "chatty --flag",
+                ],
+                [1.0, 0.0],
+            );
+        }
+        insert_hybrid_row(
+            &conn,
+            100,
+            ["quiet", "common", "Says little.", "Example", "quiet"],
+            [0.6, 0.8],
+        );
+
+        let commands = |min_commands| {
+            perform_search(
+                &conn,
+                "say something",
+                &[1.0, 0.0],
+                TargetOs::Linux,
+                false,
+                &RankingProfile::default(),
+                &QueryContext {
+                    corrections: vec![],
+                    families: &CommandFamilies::new(),
+                    scope: None,
+                    min_commands,
+                },
+            )
+            .unwrap()
+            .into_iter()
+            .map(|(cmd, _)| cmd)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(commands(1), vec!["chatty"]);
+        assert_eq!(commands(2), vec!["chatty", "quiet"]);
+        // Exhausting the table stops the widening even when the target is out of reach.
+        assert_eq!(commands(5), vec!["chatty", "quiet"]);
+    }
+
+    #[test]
+    fn lookup_page_prefers_os_page_and_lists_variants() {
+        let conn = hybrid_conn();
+        insert_hybrid_row(
+            &conn,
+            1,
+            ["ip", "common", "Generic ip.", "Common example", "ip a"],
+            [1.0, 0.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            2,
+            [
+                "ip",
+                "linux",
+                "Show network.",
+                "List addresses",
+                "ip address",
+            ],
+            [1.0, 0.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            3,
+            ["ip", "linux", "Show network.", "List routes", "ip route"],
+            [1.0, 0.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            4,
+            [
+                "ip-route",
+                "linux",
+                "Routes.",
+                "Show routes",
+                "ip route show",
+            ],
+            [1.0, 0.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            5,
+            ["ip-link", "osx", "Links.", "Show links", "ip link"],
+            [1.0, 0.0],
+        );
+
+        let page = lookup_page(&conn, "ip", TargetOs::Linux).unwrap().unwrap();
+        assert_eq!(page.platform, "linux");
+        assert_eq!(page.description, "Show network.");
+        let syntax: Vec<&str> = page.examples.iter().map(|e| e.syntax.as_str()).collect();
+        assert_eq!(syntax, vec!["ip address", "ip route"]);
+        assert_eq!(page.variants, vec!["ip-route"]);
+
+        let page = lookup_page(&conn, "ip", TargetOs::Windows)
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.platform, "common");
+        assert!(page.variants.is_empty());
+
+        assert!(
+            lookup_page(&conn, "nope", TargetOs::Linux)
+                .unwrap()
+                .is_none()
+        );
+
+        assert_eq!(
+            page_platforms(&conn, "ip").unwrap(),
+            vec!["common", "linux"]
+        );
+        assert_eq!(page_platforms(&conn, "ip-link").unwrap(), vec!["osx"]);
+        assert!(page_platforms(&conn, "nope").unwrap().is_empty());
+    }
+
+    // --- installed tools ---
+
+    #[test]
+    fn annotate_installed_keeps_ranking_with_default_weight() {
+        let mut sorted = vec![
+            ("fd".to_string(), CmdData::fixture("desc", vec![], 0.2)),
+            ("find".to_string(), CmdData::fixture("desc", vec![], 0.3)),
+        ];
+        annotate_installed(&mut sorted, &RankingProfile::default(), false, |c, _| {
+            c == "find"
+        });
+
+        assert_eq!(sorted[0].0, "fd");
+        assert!(!sorted[0].1.installed);
+        assert!(sorted[1].1.installed);
+        assert!(sorted[1].1.heuristics.is_empty());
+    }
+
+    #[test]
+    fn annotate_installed_boosts_and_filters() {
+        let mut profile = RankingProfile::default();
+        profile.weights.installed = 0.5;
+
+        let mut sorted = vec![
+            ("fd".to_string(), CmdData::fixture("desc", vec![], 0.2)),
+            ("find".to_string(), CmdData::fixture("desc", vec![], 0.3)),
+        ];
+        annotate_installed(&mut sorted, &profile, false, |c, _| c == "find");
+        assert_eq!(sorted[0].0, "find");
+        assert_eq!(sorted[0].1.heuristics, vec!["installed (0.5x)".to_string()]);
+
+        annotate_installed(&mut sorted, &RankingProfile::default(), true, |c, _| {
+            c == "find"
+        });
+        assert_eq!(sorted.len(), 1);
+        assert_eq!(sorted[0].0, "find");
+    }
+
+    // --- decomposition ---
+
+    #[test]
+    fn decompose_splits_on_sequencing_words() {
+        assert_eq!(
+            decompose_query("find large log files then compress them; upload to s3"),
+            vec!["find large log files", "compress them", "upload to s3"]
+        );
+        assert_eq!(
+            decompose_query("git stash changes && checkout main branch"),
+            vec!["git stash changes", "checkout main branch"]
+        );
+    }
+
+    #[test]
+    fn decompose_splits_and_only_between_standalone_steps() {
+        assert_eq!(
+            decompose_query("find files older than 7 days and delete them"),
+            vec!["find files older than 7 days", "delete them"]
+        );
+        assert_eq!(
+            decompose_query("search and replace text in files"),
+            vec!["search and replace text in files"]
+        );
+    }
+
+    #[test]
+    fn decompose_keeps_simple_query_whole() {
+        assert_eq!(decompose_query("extract tar.gz"), vec!["extract tar.gz"]);
+        assert_eq!(decompose_query("  ; then "), Vec::<String>::new());
+    }
+
+    // --- example relevance ---
+
+    #[test]
+    fn sort_examples_puts_closest_first_and_unscored_last() {
+        let mut examples = vec![
+            Example::new("unscored", "cmd a"),
+            Example::scored("far".to_string(), "cmd b".to_string(), 0.9),
+            Example::scored("near".to_string(), "cmd c".to_string(), 0.2),
+        ];
+        sort_examples(&mut examples);
+        let order: Vec<&str> = examples.iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(order, vec!["near", "far", "unscored"]);
+    }
+
+    #[test]
+    fn hydrated_examples_are_scored_and_ranked() {
+        let conn = hybrid_conn();
+        insert_hybrid_row(
+            &conn,
+            1,
+            [
+                "tar",
+                "common",
+                "Archiver.",
+                "List contents",
+                "tar tf {{a.tar}}",
+            ],
+            [0.0, 1.0],
+        );
+        insert_hybrid_row(
+            &conn,
+            2,
+            [
+                "tar",
+                "common",
+                "Archiver.",
+                "Extract archive",
+                "tar xf {{a.tar}}",
+            ],
+            [1.0, 0.0],
+        );
+
+        let mut data = CmdData::fixture(
+            "Archiver.",
+            vec![Example::scored(
+                "List contents".to_string(),
+                "tar tf {{a.tar}}".to_string(),
+                0.5,
+            )],
+            0.5,
+        );
+        hydrate_examples_for_command(&conn, "tar", &mut data, TargetOs::Linux, true, 12).unwrap();
+        assert_eq!(data.examples[1].distance, None);
+
+        score_examples(&conn, "tar", &mut data, &[1.0, 0.0]).unwrap();
+        assert_eq!(data.examples[0].syntax, "tar xf {{a.tar}}");
+        assert!(data.examples[0].distance.unwrap() < 0.001);
+        // Already-scored examples keep their distance.
+        assert_eq!(data.examples[1].distance, Some(0.5));
+    }
 }